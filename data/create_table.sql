@@ -1,22 +1,90 @@
 CREATE TABLE IF NOT EXISTS item (
     id INTEGER PRIMARY KEY NOT NULL,
     -- name TEXT NOT NULL,
-    amount INTEGER NOT NULL,
-    completed BOOLEAN NOT NULL
+    amount REAL NOT NULL CHECK(amount >= 0),
+    unit TEXT NOT NULL DEFAULT 'pcs', -- pcs, g, kg, ml, l or packs
+    completed BOOLEAN NOT NULL,
+    priority BOOLEAN NOT NULL DEFAULT FALSE,
+    needed_by TEXT -- YYYY-MM-DD
 );
 
 CREATE TABLE IF NOT EXISTS item_variant (
     id INTEGER PRIMARY KEY NOT NULL,
     variant_of INTEGER NOT NULL, -- foreign key -> items.id
     name TEXT NOT NULL CHECK(name <> ''),
+    name_lang TEXT NOT NULL DEFAULT '', -- language code of `name`
     -- shop_id INTEGER, -- foreign key -> shops.id
     shop TEXT NOT NULL DEFAULT '',
-    barcode INTEGER,
+    barcode TEXT, -- GTIN digits including leading zeros
     brands TEXT NOT NULL DEFAULT '',
     img_url TEXT DEFAULT '',
     thumb_url TEXT DEFAULT '',
     packaging TEXT NOT NULL DEFAULT '',
-    quantity TEXT NOT NULL DEFAULT ''
+    quantity TEXT NOT NULL DEFAULT '',
+    nutrition TEXT NOT NULL DEFAULT '{}', -- JSON, per 100 g
+    scores TEXT NOT NULL DEFAULT '{}', -- JSON, Nutri-Score, NOVA group and nutrient levels
+    dietary TEXT NOT NULL DEFAULT '{}', -- JSON, allergens, traces and ingredient analysis tags
+    package TEXT NOT NULL DEFAULT '{}' -- JSON, best before and expiry date and lot number from a GS1 code
+);
+
+CREATE TABLE IF NOT EXISTS template (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE CHECK(name <> ''),
+    items TEXT NOT NULL -- JSON array of `NewItem`s
+);
+
+CREATE TABLE IF NOT EXISTS recipe (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL CHECK(name <> ''),
+    servings INTEGER NOT NULL CHECK(servings > 0)
+);
+
+CREATE TABLE IF NOT EXISTS recipe_ingredient (
+    id INTEGER PRIMARY KEY NOT NULL,
+    ingredient_of INTEGER NOT NULL, -- foreign key -> recipe.id
+    name TEXT NOT NULL CHECK(name <> ''),
+    amount REAL NOT NULL CHECK(amount >= 0),
+    unit TEXT NOT NULL DEFAULT 'pcs',
+    barcode TEXT -- the linked product
+);
+
+CREATE TABLE IF NOT EXISTS recurrence (
+    id INTEGER PRIMARY KEY NOT NULL,
+    item_id INTEGER NOT NULL, -- foreign key -> item.id
+    item TEXT NOT NULL, -- JSON `NewItem`, used if the item has been removed
+    every INTEGER NOT NULL CHECK(every > 0),
+    unit TEXT NOT NULL CHECK(unit IN ('day', 'week', 'month')),
+    next_due TEXT NOT NULL -- YYYY-MM-DD
+);
+
+CREATE TABLE IF NOT EXISTS openfoodfacts_cache (
+    provider TEXT NOT NULL, -- e.g. "openfoodfacts" or "openbeautyfacts"
+    barcode TEXT NOT NULL,
+    product TEXT, -- JSON, NULL if the product wasn't found
+    fetched_at INTEGER NOT NULL, -- unix timestamp
+    PRIMARY KEY (provider, barcode)
+);
+
+CREATE TABLE IF NOT EXISTS catalog (
+    barcode TEXT PRIMARY KEY NOT NULL,
+    product TEXT NOT NULL -- JSON, shaped like the `product` of an OpenFoodFacts response
+);
+
+CREATE TABLE IF NOT EXISTS profile (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE CHECK(name <> ''),
+    allergens TEXT NOT NULL DEFAULT '[]', -- JSON, OpenFoodFacts tags, e.g. "en:nuts"
+    diets TEXT NOT NULL DEFAULT '[]', -- JSON
+    active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE TABLE IF NOT EXISTS contribution (
+    id INTEGER PRIMARY KEY NOT NULL,
+    contribution TEXT NOT NULL, -- JSON, the product data for OpenFoodFacts
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, done or failed
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt INTEGER NOT NULL, -- unix timestamp
+    last_error TEXT
 );
 
 -- CREATE TABLE IF NOT EXISTS shop (
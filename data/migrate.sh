@@ -0,0 +1,8 @@
+#!/usr/bin/env bash
+
+# Applies a migration from `./migrations` to an existing database.
+# usage: ./migrate.sh 0001_item_urgency.sql
+
+dir="$(dirname "${0}")"
+
+sqlite3 "$dir/ShoppingList.db" < "$dir/migrations/${1}"
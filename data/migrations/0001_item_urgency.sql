@@ -0,0 +1,5 @@
+-- Adds the `priority` and `needed_by` columns to databases created before they
+-- were part of `create_table.sql`.
+
+ALTER TABLE item ADD COLUMN priority BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE item ADD COLUMN needed_by TEXT; -- YYYY-MM-DD
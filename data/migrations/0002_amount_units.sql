@@ -0,0 +1,34 @@
+-- Changes `amount` to REAL and adds the `unit` column. SQLite can't change the
+-- type of a column, so the tables are rebuilt. Existing amounts are kept as
+-- pieces.
+
+BEGIN TRANSACTION;
+
+CREATE TABLE item_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    amount REAL NOT NULL CHECK(amount >= 0),
+    unit TEXT NOT NULL DEFAULT 'pcs', -- pcs, g, kg, ml, l or packs
+    completed BOOLEAN NOT NULL,
+    priority BOOLEAN NOT NULL DEFAULT FALSE,
+    needed_by TEXT -- YYYY-MM-DD
+);
+INSERT INTO item_new(id, amount, unit, completed, priority, needed_by)
+    SELECT id, CAST(MAX(amount, 0) AS REAL), 'pcs', completed, priority, needed_by FROM item;
+DROP TABLE item;
+ALTER TABLE item_new RENAME TO item;
+
+CREATE TABLE recipe_ingredient_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    ingredient_of INTEGER NOT NULL, -- foreign key -> recipe.id
+    name TEXT NOT NULL CHECK(name <> ''),
+    amount REAL NOT NULL CHECK(amount >= 0),
+    unit TEXT NOT NULL DEFAULT 'pcs',
+    barcode INTEGER -- the linked product
+);
+INSERT INTO recipe_ingredient_new(id, ingredient_of, name, amount, unit, barcode)
+    SELECT id, ingredient_of, name, CAST(MAX(amount, 0) AS REAL), 'pcs', barcode
+    FROM recipe_ingredient;
+DROP TABLE recipe_ingredient;
+ALTER TABLE recipe_ingredient_new RENAME TO recipe_ingredient;
+
+COMMIT;
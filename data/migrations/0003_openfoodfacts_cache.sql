@@ -0,0 +1,7 @@
+-- Adds the cache for OpenFoodFacts responses.
+
+CREATE TABLE IF NOT EXISTS openfoodfacts_cache (
+    barcode INTEGER PRIMARY KEY NOT NULL,
+    product TEXT, -- JSON, NULL if the product wasn't found
+    fetched_at INTEGER NOT NULL -- unix timestamp
+);
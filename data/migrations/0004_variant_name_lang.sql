@@ -0,0 +1,3 @@
+-- Stores the language of the variant names.
+
+ALTER TABLE item_variant ADD COLUMN name_lang TEXT NOT NULL DEFAULT ''; -- language code of `name`
@@ -0,0 +1,3 @@
+-- Stores the nutrition facts of the variants.
+
+ALTER TABLE item_variant ADD COLUMN nutrition TEXT NOT NULL DEFAULT '{}'; -- JSON, per 100 g
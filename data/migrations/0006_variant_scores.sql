@@ -0,0 +1,3 @@
+-- Stores the Nutri-Score, NOVA group and nutrient levels of the variants.
+
+ALTER TABLE item_variant ADD COLUMN scores TEXT NOT NULL DEFAULT '{}'; -- JSON
@@ -0,0 +1,12 @@
+-- Adds allergen and diet profiles and stores the matching product data of the
+-- variants.
+
+CREATE TABLE IF NOT EXISTS profile (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE CHECK(name <> ''),
+    allergens TEXT NOT NULL DEFAULT '[]', -- JSON, OpenFoodFacts tags, e.g. "en:nuts"
+    diets TEXT NOT NULL DEFAULT '[]', -- JSON
+    active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+ALTER TABLE item_variant ADD COLUMN dietary TEXT NOT NULL DEFAULT '{}'; -- JSON
@@ -0,0 +1,23 @@
+-- Separates the cache entries by provider and adds the local product catalog.
+-- Existing cache entries came from OpenFoodFacts.
+
+BEGIN TRANSACTION;
+
+CREATE TABLE openfoodfacts_cache_new (
+    provider TEXT NOT NULL, -- e.g. "openfoodfacts" or "openbeautyfacts"
+    barcode INTEGER NOT NULL,
+    product TEXT, -- JSON, NULL if the product wasn't found
+    fetched_at INTEGER NOT NULL, -- unix timestamp
+    PRIMARY KEY (provider, barcode)
+);
+INSERT INTO openfoodfacts_cache_new(provider, barcode, product, fetched_at)
+    SELECT 'openfoodfacts', barcode, product, fetched_at FROM openfoodfacts_cache;
+DROP TABLE openfoodfacts_cache;
+ALTER TABLE openfoodfacts_cache_new RENAME TO openfoodfacts_cache;
+
+CREATE TABLE IF NOT EXISTS catalog (
+    barcode INTEGER PRIMARY KEY NOT NULL,
+    product TEXT NOT NULL -- JSON, shaped like the `product` of an OpenFoodFacts response
+);
+
+COMMIT;
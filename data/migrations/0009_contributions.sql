@@ -0,0 +1,10 @@
+-- Adds the queue of products which are submitted to OpenFoodFacts.
+
+CREATE TABLE IF NOT EXISTS contribution (
+    id INTEGER PRIMARY KEY NOT NULL,
+    contribution TEXT NOT NULL, -- JSON, the product data for OpenFoodFacts
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, done or failed
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt INTEGER NOT NULL, -- unix timestamp
+    last_error TEXT
+);
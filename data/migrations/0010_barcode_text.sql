@@ -0,0 +1,91 @@
+-- Stores barcodes as digit strings, so EAN-8 and EAN-13 codes with leading
+-- zeros keep their length. SQLite can't change the type of a column, so the
+-- tables are rebuilt. Barcodes in JSON columns are converted when they are
+-- loaded.
+
+BEGIN TRANSACTION;
+
+CREATE TABLE item_variant_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    variant_of INTEGER NOT NULL, -- foreign key -> items.id
+    name TEXT NOT NULL CHECK(name <> ''),
+    name_lang TEXT NOT NULL DEFAULT '', -- language code of `name`
+    -- shop_id INTEGER, -- foreign key -> shops.id
+    shop TEXT NOT NULL DEFAULT '',
+    barcode TEXT, -- GTIN digits including leading zeros
+    brands TEXT NOT NULL DEFAULT '',
+    img_url TEXT DEFAULT '',
+    thumb_url TEXT DEFAULT '',
+    packaging TEXT NOT NULL DEFAULT '',
+    quantity TEXT NOT NULL DEFAULT '',
+    nutrition TEXT NOT NULL DEFAULT '{}', -- JSON, per 100 g
+    scores TEXT NOT NULL DEFAULT '{}', -- JSON, Nutri-Score, NOVA group and nutrient levels
+    dietary TEXT NOT NULL DEFAULT '{}' -- JSON, allergens, traces and ingredient analysis tags
+);
+INSERT INTO item_variant_new
+    SELECT id, variant_of, name, name_lang, shop,
+        CASE
+            WHEN typeof(barcode) <> 'integer' THEN barcode
+            WHEN length(barcode) <= 8 THEN printf('%08d', barcode)
+            ELSE printf('%013d', barcode)
+        END,
+        brands, img_url, thumb_url, packaging, quantity, nutrition, scores, dietary
+    FROM item_variant;
+DROP TABLE item_variant;
+ALTER TABLE item_variant_new RENAME TO item_variant;
+
+CREATE TABLE recipe_ingredient_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    ingredient_of INTEGER NOT NULL, -- foreign key -> recipe.id
+    name TEXT NOT NULL CHECK(name <> ''),
+    amount REAL NOT NULL CHECK(amount >= 0),
+    unit TEXT NOT NULL DEFAULT 'pcs',
+    barcode TEXT -- the linked product
+);
+INSERT INTO recipe_ingredient_new
+    SELECT id, ingredient_of, name, amount, unit,
+        CASE
+            WHEN typeof(barcode) <> 'integer' THEN barcode
+            WHEN length(barcode) <= 8 THEN printf('%08d', barcode)
+            ELSE printf('%013d', barcode)
+        END
+    FROM recipe_ingredient;
+DROP TABLE recipe_ingredient;
+ALTER TABLE recipe_ingredient_new RENAME TO recipe_ingredient;
+
+CREATE TABLE openfoodfacts_cache_new (
+    provider TEXT NOT NULL, -- e.g. "openfoodfacts" or "openbeautyfacts"
+    barcode TEXT NOT NULL,
+    product TEXT, -- JSON, NULL if the product wasn't found
+    fetched_at INTEGER NOT NULL, -- unix timestamp
+    PRIMARY KEY (provider, barcode)
+);
+INSERT INTO openfoodfacts_cache_new
+    SELECT provider,
+        CASE
+            WHEN typeof(barcode) <> 'integer' THEN barcode
+            WHEN length(barcode) <= 8 THEN printf('%08d', barcode)
+            ELSE printf('%013d', barcode)
+        END,
+        product, fetched_at
+    FROM openfoodfacts_cache;
+DROP TABLE openfoodfacts_cache;
+ALTER TABLE openfoodfacts_cache_new RENAME TO openfoodfacts_cache;
+
+CREATE TABLE catalog_new (
+    barcode TEXT PRIMARY KEY NOT NULL,
+    product TEXT NOT NULL -- JSON, shaped like the `product` of an OpenFoodFacts response
+);
+INSERT INTO catalog_new
+    SELECT
+        CASE
+            WHEN typeof(barcode) <> 'integer' THEN barcode
+            WHEN length(barcode) <= 8 THEN printf('%08d', barcode)
+            ELSE printf('%013d', barcode)
+        END,
+        product
+    FROM catalog;
+DROP TABLE catalog;
+ALTER TABLE catalog_new RENAME TO catalog;
+
+COMMIT;
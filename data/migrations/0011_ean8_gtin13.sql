@@ -0,0 +1,12 @@
+-- Stores EAN-8 codes as their GTIN-13 with five leading zeros, so they match
+-- the same product scanned from a GS1 DataMatrix or typed with 13 digits.
+-- Barcodes in JSON columns are converted when they are loaded.
+
+BEGIN TRANSACTION;
+
+UPDATE item_variant SET barcode = '00000' || barcode WHERE length(barcode) = 8;
+UPDATE recipe_ingredient SET barcode = '00000' || barcode WHERE length(barcode) = 8;
+UPDATE OR REPLACE openfoodfacts_cache SET barcode = '00000' || barcode WHERE length(barcode) = 8;
+UPDATE OR REPLACE catalog SET barcode = '00000' || barcode WHERE length(barcode) = 8;
+
+COMMIT;
@@ -0,0 +1,4 @@
+-- Stores the best before and expiry date and the lot number of GS1 DataMatrix
+-- and QR codes.
+
+ALTER TABLE item_variant ADD COLUMN package TEXT NOT NULL DEFAULT '{}'; -- JSON, best before and expiry date and lot number from a GS1 code
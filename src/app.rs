@@ -6,6 +6,9 @@ use crate::{
     language::Language,
     login::LoginView,
     main_page::MainPage,
+    profile::ProfilesPage,
+    recipe::RecipesPage,
+    recurrence::RecurrencesPage,
 };
 use leptos::*;
 use leptos_meta::*;
@@ -88,6 +91,9 @@ pub fn App() -> impl IntoView {
                     />
                     <Route path="/login" view=LoginView/>
                     <Route path="/db" view=DBTool/>
+                    <Route path="/recipes" view=RecipesPage/>
+                    <Route path="/recurring" view=RecurrencesPage/>
+                    <Route path="/profiles" view=ProfilesPage/>
                 </Routes>
             </main>
         </Router>
@@ -1,109 +1,210 @@
 use super::BarcodeError;
-use crate::util::DecLen;
-use serde::{Deserialize, Serialize};
-use std::{
-    fmt,
-    num::{NonZeroI64, ParseIntError},
-};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// A validated product code. The symbology defines the number of digits, so
+/// leading zeros are kept.
+///
+/// Barcodes are compared, stored and looked up by their [`Barcode::gtin`], so
+/// a product scanned as UPC-A matches the same product entered as EAN-13.
+#[derive(Debug, Clone, Copy)]
 pub struct Barcode {
-    digits: NonZeroI64,
+    digits: u64,
+    kind: BarcodeType,
 }
 
-#[cfg(feature = "ssr")]
-impl sqlx::Type<sqlx::Sqlite> for Barcode {
-    fn type_info() -> <sqlx::Sqlite as sqlx::Database>::TypeInfo {
-        <i64 as sqlx::Type<sqlx::Sqlite>>::type_info()
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BarcodeType {
+    Ean13,
+    Ean8,
+    UpcA,
+    /// The zero-suppressed form of a UPC-A code.
+    UpcE,
+    Gtin14,
 }
 
-#[cfg(feature = "ssr")]
-impl sqlx::Encode<'_, sqlx::Sqlite> for Barcode {
-    fn encode_by_ref(
-        &self,
-        buf: &mut <sqlx::Sqlite as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
-    ) -> sqlx::encode::IsNull {
-        let int = self.digits.get() as i64;
-        <i64 as sqlx::Encode<'_, sqlx::Sqlite>>::encode_by_ref(&int, buf)
+impl BarcodeType {
+    /// The number of digits, including the check digit.
+    pub fn len(self) -> u8 {
+        match self {
+            BarcodeType::Ean13 => 13,
+            BarcodeType::Ean8 | BarcodeType::UpcE => 8,
+            BarcodeType::UpcA => 12,
+            BarcodeType::Gtin14 => 14,
+        }
+    }
+
+    /// 8 digits are ambiguous, EAN-8 is preferred over UPC-E.
+    fn from_len(len: usize) -> Option<BarcodeType> {
+        match len {
+            13 => Some(BarcodeType::Ean13),
+            8 => Some(BarcodeType::Ean8),
+            12 => Some(BarcodeType::UpcA),
+            14 => Some(BarcodeType::Gtin14),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum BarcodeType {
-    Ean13,
-    Ean8,
+/// The GTIN check digit of `payload`, the digits without the check digit.
+/// Starting from the right, the digits are weighted 3, 1, 3, …
+pub fn check_digit(payload: &[u8]) -> u8 {
+    let sum: u32 = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| *d as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// Expands the 8 digits of a UPC-E code to the 12 digits of its UPC-A code.
+/// The last of the six middle digits defines where the zeros were removed.
+fn expand_upc_e(digits: &[u8]) -> Vec<u8> {
+    let (number_system, d, check) = (digits[0], &digits[1..7], digits[7]);
+    let middle = match d[5] {
+        0..=2 => [d[0], d[1], d[5], 0, 0, 0, 0, d[2], d[3], d[4]],
+        3 => [d[0], d[1], d[2], 0, 0, 0, 0, 0, d[3], d[4]],
+        4 => [d[0], d[1], d[2], d[3], 0, 0, 0, 0, 0, d[4]],
+        _ => [d[0], d[1], d[2], d[3], d[4], 0, 0, 0, 0, d[5]],
+    };
+    [&[number_system][..], &middle, &[check]].concat()
 }
 
 impl Barcode {
-    pub fn ean13(digits: u64) -> Result<Barcode, BarcodeError> {
-        let dec_len = digits.dec_len();
-        if dec_len != 13 {
-            return Err(BarcodeError::InvalidBarcodeLength { expected: 13, got: dec_len });
+    /// Validates the length, the characters and the check digit of `digits`.
+    pub fn new(digits: &str, kind: BarcodeType) -> Result<Barcode, BarcodeError> {
+        let barcode = Barcode::unchecked(digits, kind)?;
+        if kind == BarcodeType::UpcE && !digits.starts_with(['0', '1']) {
+            return Err(BarcodeError::InvalidNumberSystem(digits.to_string()));
+        }
+        let gtin = barcode.gtin_values();
+        let (payload, got) = gtin.split_at(gtin.len() - 1);
+        let expected = check_digit(payload);
+        if got[0] != expected {
+            return Err(BarcodeError::InvalidCheckDigit { expected, got: got[0] });
         }
-        let digits =
-            NonZeroI64::new(digits.try_into()?).ok_or(BarcodeError::InvalidBarcode(digits))?;
-        // TODO: more checks?
-        Ok(Barcode { digits })
+        Ok(barcode)
     }
 
-    pub fn ean8(digits: u64) -> Result<Barcode, BarcodeError> {
-        let dec_len = digits.dec_len();
-        if dec_len != 8 {
-            return Err(BarcodeError::InvalidBarcodeLength { expected: 8, got: dec_len });
+    /// Like [`Barcode::new`], but doesn't validate the check digit.
+    fn unchecked(digits: &str, kind: BarcodeType) -> Result<Barcode, BarcodeError> {
+        if digits.len() != kind.len() as usize {
+            let got = digits.len().min(u8::MAX as usize) as u8;
+            return Err(BarcodeError::InvalidBarcodeLength { expected: kind.len(), got });
         }
-        let digits =
-            NonZeroI64::new(digits.try_into()?).ok_or(BarcodeError::InvalidBarcode(digits))?;
-        // TODO: more checks?
-        Ok(Barcode { digits })
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(BarcodeError::NotDigits(digits.to_string()));
+        }
+        let digits = digits.parse().map_err(|_| BarcodeError::NotDigits(digits.to_string()))?;
+        Ok(Barcode { digits, kind })
     }
 
-    pub fn get_digits(&self) -> i64 {
-        self.digits.get()
+    pub fn ean13(digits: &str) -> Result<Barcode, BarcodeError> {
+        Barcode::new(digits, BarcodeType::Ean13)
     }
 
-    pub fn get_type(self) -> BarcodeType {
-        match (self.digits.get() as u64).dec_len() {
-            8 => BarcodeType::Ean8,
-            13 => BarcodeType::Ean13,
-            _ => unreachable!(),
+    pub fn ean8(digits: &str) -> Result<Barcode, BarcodeError> {
+        Barcode::new(digits, BarcodeType::Ean8)
+    }
+
+    /// Guesses the symbology from the number of digits. 8 digits are read as
+    /// UPC-E if they aren't a valid EAN-8 code.
+    pub fn from_digits(digits: &str) -> Result<Barcode, BarcodeError> {
+        match BarcodeType::from_len(digits.len()) {
+            Some(BarcodeType::Ean8) => Barcode::ean8(digits)
+                .or_else(|err| Barcode::new(digits, BarcodeType::UpcE).map_err(|_| err)),
+            Some(kind) => Barcode::new(digits, kind),
+            None => Err(BarcodeError::InvalidBarcodeLength {
+                expected: BarcodeType::Ean13.len(),
+                got: digits.len().min(u8::MAX as usize) as u8,
+            }),
         }
     }
-}
 
-impl From<u64> for Barcode {
-    /// # Panics
-    ///
-    /// Panics if the `digits` aren't a valid Barcode
-    fn from(digits: u64) -> Self {
-        match digits.dec_len() {
-            8 => Barcode::ean8(digits).unwrap(),
-            13 => Barcode::ean13(digits).unwrap(),
-            len => panic!("invalid number of digits: {}", len),
+    /// Barcodes from before the check digit was validated are still accepted
+    /// when they are loaded or deserialized.
+    fn from_stored(digits: &str) -> Result<Barcode, BarcodeError> {
+        match BarcodeType::from_len(digits.len()) {
+            Some(kind) => Barcode::unchecked(digits, kind),
+            None => Barcode::from_digits(digits),
+        }
+    }
+
+    /// The digits as printed on the product, including leading zeros.
+    pub fn get_digits(&self) -> String {
+        format!("{:0width$}", self.digits, width = self.kind.len() as usize)
+    }
+
+    fn digit_values(&self) -> Vec<u8> {
+        self.get_digits().bytes().map(|b| b - b'0').collect()
+    }
+
+    fn gtin_values(&self) -> Vec<u8> {
+        let digits = self.digit_values();
+        match self.kind {
+            BarcodeType::Ean13 => digits,
+            BarcodeType::Ean8 => [&[0; 5][..], &digits].concat(),
+            BarcodeType::UpcA => [&[0][..], &digits].concat(),
+            BarcodeType::UpcE => [&[0][..], &expand_upc_e(&digits)].concat(),
+            BarcodeType::Gtin14 if digits[0] == 0 => digits[1..].to_vec(),
+            BarcodeType::Gtin14 => digits,
         }
     }
+
+    /// The normalized code which is used for storage and lookups: EAN-8, UPC
+    /// and GTIN-14 codes without a packaging indicator become GTIN-13.
+    pub fn gtin(&self) -> String {
+        self.gtin_values().into_iter().map(|d| char::from(b'0' + d)).collect()
+    }
+
+    /// The 8 digits of an EAN-8 code, also if it was entered or scanned as
+    /// GTIN-13 or GTIN-14 with leading zeros.
+    pub fn ean8_digits(&self) -> Option<String> {
+        self.gtin().strip_prefix("00000").map(str::to_string)
+    }
+
+    /// The code which OpenFoodFacts knows the product by. It keeps the 8
+    /// digits of EAN-8 codes.
+    pub fn openfoodfacts_code(&self) -> String {
+        self.ean8_digits().unwrap_or_else(|| self.gtin())
+    }
+
+    pub fn get_type(self) -> BarcodeType {
+        self.kind
+    }
 }
 
-impl From<i64> for Barcode {
-    /// # Panics
-    ///
-    /// Panics if the `digits` aren't a valid Barcode
-    fn from(digits: i64) -> Self {
-        Barcode::from(digits as u64)
+impl PartialEq for Barcode {
+    fn eq(&self, other: &Self) -> bool {
+        self.gtin() == other.gtin()
     }
 }
 
+impl Eq for Barcode {}
+
+impl std::hash::Hash for Barcode {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.gtin().hash(state)
+    }
+}
+
+/// Shows the [`Barcode::gtin`].
 impl fmt::Display for Barcode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.get_digits())
+        write!(f, "{}", self.gtin())
     }
 }
 
-impl TryFrom<String> for Barcode {
-    type Error = ParseIntError;
+/// Parses user input like "4 002674 044119" or "4002674-044119". Spaces and
+/// dashes are ignored, the symbology is guessed like in
+/// [`Barcode::from_digits`].
+impl FromStr for Barcode {
+    type Err = BarcodeError;
 
-    fn try_from(text: String) -> Result<Self, Self::Error> {
-        text.parse::<u64>().map(Barcode::from)
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let digits: String = text.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+        Barcode::from_digits(&digits)
     }
 }
 
@@ -111,15 +212,71 @@ impl TryFrom<rxing_wasm::BarcodeResult> for Barcode {
     type Error = BarcodeError;
 
     fn try_from(value: rxing_wasm::BarcodeResult) -> Result<Self, Self::Error> {
-        let digits = value.text().parse().map_err(BarcodeError::ParseBarcodeErr);
         match value.format() {
-            rxing_wasm::BarcodeFormat::Ean8 => digits.and_then(Barcode::ean8),
-            rxing_wasm::BarcodeFormat::Ean13 => digits.and_then(Barcode::ean13),
+            rxing_wasm::BarcodeFormat::Ean8 => Barcode::ean8(&value.text()),
+            rxing_wasm::BarcodeFormat::Ean13 => Barcode::ean13(&value.text()),
+            rxing_wasm::BarcodeFormat::UpcA => Barcode::new(&value.text(), BarcodeType::UpcA),
+            rxing_wasm::BarcodeFormat::UpcE => Barcode::new(&value.text(), BarcodeType::UpcE),
             f => Err(BarcodeError::UnsupportedBarcodeType(format!("{:?}", f))),
         }
     }
 }
 
+/// Serialized as the [`Barcode::gtin`].
+impl Serialize for Barcode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.gtin())
+    }
+}
+
+/// Also accepts the old representation `{ "digits": 4002674044119 }`, which is
+/// still stored in JSON columns. Like the stored barcodes, the check digit
+/// isn't validated, because the values come from the database and not from
+/// the user.
+impl<'de> Deserialize<'de> for Barcode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Digits(String),
+            Old { digits: u64 },
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Digits(digits) => Barcode::from_stored(&digits),
+            Repr::Old { digits } => Barcode::from_stored(&digits.to_string()),
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl sqlx::Type<sqlx::Sqlite> for Barcode {
+    fn type_info() -> <sqlx::Sqlite as sqlx::Database>::TypeInfo {
+        <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl sqlx::Encode<'_, sqlx::Sqlite> for Barcode {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <sqlx::Sqlite as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <String as sqlx::Encode<'_, sqlx::Sqlite>>::encode(self.gtin(), buf)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl sqlx::Decode<'_, sqlx::Sqlite> for Barcode {
+    fn decode(
+        value: <sqlx::Sqlite as sqlx::database::HasValueRef<'_>>::ValueRef,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        let digits = <&str as sqlx::Decode<'_, sqlx::Sqlite>>::decode(value)?;
+        Ok(Barcode::from_stored(digits)?)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct OptionBarcode(pub Option<Barcode>);
@@ -143,11 +300,11 @@ impl<T: Into<Barcode>> From<Option<T>> for OptionBarcode {
 #[cfg(feature = "ssr")]
 impl sqlx::Type<sqlx::Sqlite> for OptionBarcode {
     fn type_info() -> <sqlx::Sqlite as sqlx::Database>::TypeInfo {
-        <i64 as sqlx::Type<sqlx::Sqlite>>::type_info()
+        <String as sqlx::Type<sqlx::Sqlite>>::type_info()
     }
 
     fn compatible(ty: &<sqlx::Sqlite as sqlx::Database>::TypeInfo) -> bool {
-        <i64 as sqlx::Type<sqlx::Sqlite>>::compatible(ty)
+        <String as sqlx::Type<sqlx::Sqlite>>::compatible(ty)
     }
 }
 
@@ -166,7 +323,127 @@ impl sqlx::Decode<'_, sqlx::Sqlite> for OptionBarcode {
     fn decode(
         value: <sqlx::Sqlite as sqlx::database::HasValueRef<'_>>::ValueRef,
     ) -> Result<Self, sqlx::error::BoxDynError> {
-        let int = <i64 as sqlx::Decode<'_, sqlx::Sqlite>>::decode(value)?;
-        Ok(OptionBarcode(NonZeroI64::new(int).map(|digits| Barcode { digits })))
+        let digits = <Option<&str> as sqlx::Decode<'_, sqlx::Sqlite>>::decode(value)?;
+        let digits = digits.filter(|d| !d.trim().is_empty());
+        Ok(OptionBarcode(digits.map(Barcode::from_stored).transpose()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_leading_zeros() {
+        let barcode = Barcode::ean13("0012345678905").unwrap();
+        assert_eq!(barcode.to_string(), "0012345678905");
+        assert_eq!(barcode.get_type(), BarcodeType::Ean13);
+        let json = serde_json::to_string(&barcode).unwrap();
+        assert_eq!(json, "\"0012345678905\"");
+        assert_eq!(serde_json::from_str::<Barcode>(&json).unwrap(), barcode);
+        assert_eq!(Barcode::ean8("00123457").unwrap().get_digits(), "00123457");
+    }
+
+    #[test]
+    fn test_ean8() {
+        let ean8 = Barcode::ean8("96385074").unwrap();
+        assert_eq!(ean8.gtin(), "0000096385074");
+        assert_eq!(ean8, Barcode::ean13("0000096385074").unwrap());
+        assert_eq!(ean8, Barcode::new("00000096385074", BarcodeType::Gtin14).unwrap());
+        assert_eq!(ean8, "0000096385074".parse().unwrap());
+        assert_eq!(ean8.openfoodfacts_code(), "96385074");
+        let gtin14 = Barcode::new("00000096385074", BarcodeType::Gtin14).unwrap();
+        assert_eq!(gtin14.openfoodfacts_code(), "96385074");
+        assert_eq!(Barcode::ean13("4002674044119").unwrap().openfoodfacts_code(), "4002674044119");
+    }
+
+    #[test]
+    fn test_check_digit() {
+        assert!(Barcode::ean13("4002674044119").is_ok());
+        assert!(matches!(
+            Barcode::ean13("4002674044118"),
+            Err(BarcodeError::InvalidCheckDigit { expected: 9, got: 8 })
+        ));
+        assert!(Barcode::ean8("96385074").is_ok());
+        assert!(Barcode::ean8("96385075").is_err());
+    }
+
+    #[test]
+    fn test_upc() {
+        let ean13 = Barcode::ean13("0042100005264").unwrap();
+        let upc_a = Barcode::new("042100005264", BarcodeType::UpcA).unwrap();
+        let upc_e = Barcode::new("04252614", BarcodeType::UpcE).unwrap();
+        assert_eq!(upc_e.gtin(), "0042100005264");
+        assert_eq!(upc_e.get_digits(), "04252614");
+        assert_eq!((upc_a, upc_e), (ean13, ean13));
+        assert_eq!(Barcode::from_digits("04252614").unwrap().get_type(), BarcodeType::UpcE);
+        assert!(Barcode::new("24252614", BarcodeType::UpcE).is_err());
+
+        let gtin14 = Barcode::new("04002674044119", BarcodeType::Gtin14).unwrap();
+        assert_eq!(gtin14.to_string(), "4002674044119");
+        let case = Barcode::from_digits("14002674044116").unwrap();
+        assert_eq!(case.to_string(), "14002674044116");
+    }
+
+    #[test]
+    fn test_unchecked_stored() {
+        let stored = Barcode::from_stored("4002674044118").unwrap();
+        let json = serde_json::to_string(&stored).unwrap();
+        assert_eq!(serde_json::from_str::<Barcode>(&json).unwrap(), stored);
+        assert!(json.trim_matches('"').parse::<Barcode>().is_err());
+    }
+
+    #[test]
+    fn test_old_json() {
+        let barcode = serde_json::from_str::<Barcode>(r#"{"digits":4002674044119}"#).unwrap();
+        assert_eq!(barcode, Barcode::ean13("4002674044119").unwrap());
+    }
+
+    /// A valid barcode of the given length with leading zeros.
+    fn valid_digits(len: usize) -> impl Strategy<Value = String> {
+        proptest::collection::vec(0..10u8, len - 1).prop_map(|mut digits| {
+            digits.push(check_digit(&digits));
+            digits.into_iter().map(|d| char::from(b'0' + d)).collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn parse_never_panics(text in "\\PC*") {
+            let _ = text.parse::<Barcode>();
+        }
+
+        #[test]
+        fn parse_numbers(n in any::<u64>()) {
+            let _ = n.to_string().parse::<Barcode>();
+        }
+
+        #[test]
+        fn parse_valid(
+            digits in prop_oneof![valid_digits(12), valid_digits(13), valid_digits(14)]
+        ) {
+            let barcode = digits.parse::<Barcode>().unwrap();
+            prop_assert_eq!(barcode.get_digits(), digits.clone());
+            prop_assert_eq!(barcode.gtin().trim_start_matches('0'), digits.trim_start_matches('0'));
+            prop_assert_eq!(barcode.to_string().parse::<Barcode>().unwrap(), barcode);
+        }
+
+        #[test]
+        fn parse_ignores_separators(digits in valid_digits(13), at in 1..12usize) {
+            let separated = format!(" {}- {} ", &digits[..at], &digits[at..]);
+            prop_assert_eq!(separated.parse::<Barcode>().unwrap(), digits.parse().unwrap());
+        }
+
+        #[test]
+        fn parse_rejects_wrong_check_digit(digits in valid_digits(13), wrong in 1..10u8) {
+            let check = (digits.as_bytes()[12] - b'0' + wrong) % 10;
+            let digits = format!("{}{}", &digits[..12], check);
+            let is_check_digit_error = matches!(
+                digits.parse::<Barcode>(),
+                Err(BarcodeError::InvalidCheckDigit { .. })
+            );
+            prop_assert!(is_check_digit_error);
+        }
     }
 }
@@ -1,5 +1,4 @@
 use crate::camera::CameraError;
-use std::num::{ParseIntError, TryFromIntError};
 use wasm_bindgen::JsValue;
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -30,15 +29,21 @@ pub enum BarcodeError {
     #[error("unsupported barcode type: {}", .0)]
     UnsupportedBarcodeType(String),
 
-    #[error("can't parse barcode: {}", .0)]
-    ParseBarcodeErr(ParseIntError),
+    #[error("the barcode may only contain digits: {:?}", .0)]
+    NotDigits(String),
 
     #[error("invalid barcode length (expected: {expected}, got: {got})")]
     InvalidBarcodeLength { expected: u8, got: u8 },
 
-    #[error("invalid barcode: {}", .0)]
-    InvalidBarcode(u64),
+    #[error("invalid check digit (expected: {expected}, got: {got})")]
+    InvalidCheckDigit { expected: u8, got: u8 },
 
-    #[error(transparent)]
-    TryFromIntError(#[from] TryFromIntError),
+    #[error("UPC-E codes start with 0 or 1: {}", .0)]
+    InvalidNumberSystem(String),
+
+    #[error("invalid GS1 data: {:?}", .0)]
+    InvalidGs1(String),
+
+    #[error("the code doesn't contain a GTIN")]
+    MissingGtin,
 }
@@ -0,0 +1,247 @@
+//! Parses the GS1 application identifiers (AIs) in the DataMatrix and QR codes
+//! on fresh meat and pharmacy products, e.g. the GTIN, the best before date
+//! and the lot number.
+//!
+//! Three notations are accepted:
+//! - the raw data of the code, where variable-length elements are terminated
+//!   by a group separator: `010401234567890117250630107LOT\x1d21XYZ`
+//! - the human readable form: `(01)04012345678901(17)250630(10)7LOT`
+//! - GS1 Digital Link URLs: `https://id.gs1.org/01/04012345678901/10/7LOT?17=250630`
+
+use super::{Barcode, BarcodeError, BarcodeType};
+use chrono::{Days, Months, NaiveDate};
+
+/// The group separator (FNC1) which terminates variable-length elements.
+const GS: char = '\u{1d}';
+
+/// The symbology identifiers which scanners may prepend to GS1 data.
+const SYMBOLOGY_IDS: [&str; 4] = ["]d2", "]Q3", "]C1", "]e0"];
+
+/// The data of a GS1 code. AIs without a field are kept in `other`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gs1Data {
+    /// AI 01
+    pub gtin: Option<Barcode>,
+    /// AI 10
+    pub batch: Option<String>,
+    /// AI 11
+    pub production_date: Option<NaiveDate>,
+    /// AI 13
+    pub packaging_date: Option<NaiveDate>,
+    /// AI 15
+    pub best_before: Option<NaiveDate>,
+    /// AI 16
+    pub sell_by: Option<NaiveDate>,
+    /// AI 17
+    pub expiry: Option<NaiveDate>,
+    /// AI 21
+    pub serial: Option<String>,
+    /// AI 310n
+    pub net_weight_kg: Option<f64>,
+    pub other: Vec<(String, String)>,
+}
+
+impl Gs1Data {
+    pub fn parse(text: &str) -> Result<Gs1Data, BarcodeError> {
+        let text = text.trim();
+        let elements = if text.starts_with('(') {
+            parse_human_readable(text)?
+        } else if text.starts_with("http://") || text.starts_with("https://") {
+            parse_digital_link(text)?
+        } else {
+            parse_raw(text)?
+        };
+        if elements.is_empty() {
+            return Err(invalid(text));
+        }
+
+        let mut data = Gs1Data::default();
+        for (ai, value) in elements {
+            match ai.as_str() {
+                "01" => data.gtin = Some(Barcode::new(&value, BarcodeType::Gtin14)?),
+                "10" => data.batch = Some(value),
+                "11" => data.production_date = Some(parse_date(&value)?),
+                "13" => data.packaging_date = Some(parse_date(&value)?),
+                "15" => data.best_before = Some(parse_date(&value)?),
+                "16" => data.sell_by = Some(parse_date(&value)?),
+                "17" => data.expiry = Some(parse_date(&value)?),
+                "21" => data.serial = Some(value),
+                ai if ai.len() == 4 && ai.starts_with("310") => {
+                    let decimals = ai.chars().nth(3).and_then(|c| c.to_digit(10));
+                    let decimals = decimals.ok_or_else(|| invalid(ai))? as i32;
+                    let weight = value.parse::<u32>().map_err(|_| invalid(&value))?;
+                    data.net_weight_kg = Some(weight as f64 / 10f64.powi(decimals));
+                },
+                _ => data.other.push((ai, value)),
+            }
+        }
+        Ok(data)
+    }
+}
+
+fn invalid(text: &str) -> BarcodeError {
+    BarcodeError::InvalidGs1(text.to_string())
+}
+
+/// The number of digits of the AIs starting with `prefix`.
+fn ai_len(prefix: &str) -> usize {
+    match prefix {
+        "23" | "24" | "25" | "40" | "41" | "42" | "71" => 3,
+        "31" | "32" | "33" | "34" | "35" | "36" | "39" | "43" | "70" | "72" | "80" | "81"
+        | "82" => 4,
+        _ => 2,
+    }
+}
+
+/// The number of data characters of the AIs with a predefined length. All
+/// other elements are terminated by a [`GS`] or the end of the data.
+fn predefined_len(ai: &str) -> Option<usize> {
+    match &ai[..2] {
+        "00" => Some(18),
+        "01" | "02" | "03" => Some(14),
+        "04" => Some(16),
+        "11" | "12" | "13" | "14" | "15" | "16" | "17" | "18" | "19" => Some(6),
+        "20" => Some(2),
+        "31" | "32" | "33" | "34" | "35" | "36" => Some(6),
+        "41" => Some(13),
+        _ => None,
+    }
+}
+
+fn parse_raw(text: &str) -> Result<Vec<(String, String)>, BarcodeError> {
+    let mut rest = SYMBOLOGY_IDS.iter().find_map(|id| text.strip_prefix(id)).unwrap_or(text);
+    let mut elements = Vec::new();
+    while let Some(data) = rest.strip_prefix(GS).or(Some(rest)).filter(|r| !r.is_empty()) {
+        let prefix = data.get(..2).ok_or_else(|| invalid(text))?;
+        let ai = data.get(..ai_len(prefix)).ok_or_else(|| invalid(text))?;
+        if !ai.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid(text));
+        }
+        let data = &data[ai.len()..];
+        let len = match predefined_len(ai) {
+            Some(len) => len,
+            None => data.find(GS).unwrap_or(data.len()),
+        };
+        let value = data.get(..len).ok_or_else(|| invalid(text))?;
+        elements.push((ai.to_string(), value.to_string()));
+        rest = &data[len..];
+    }
+    Ok(elements)
+}
+
+fn parse_human_readable(text: &str) -> Result<Vec<(String, String)>, BarcodeError> {
+    text.split('(')
+        .skip(1)
+        .map(|element| match element.split_once(')') {
+            Some((ai, value)) if ai.bytes().all(|b| b.is_ascii_digit()) => {
+                Ok((ai.to_string(), value.trim().to_string()))
+            },
+            _ => Err(invalid(text)),
+        })
+        .collect()
+}
+
+/// Reads the AIs from the path, starting at the GTIN, and from the query.
+fn parse_digital_link(url: &str) -> Result<Vec<(String, String)>, BarcodeError> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments = path.split('/').skip_while(|s| *s != "01").collect::<Vec<_>>();
+    if segments.len() % 2 != 0 {
+        return Err(invalid(url));
+    }
+    let path = segments.chunks(2).map(|c| (c[0], c[1]));
+    let is_ai = |ai: &str| !ai.is_empty() && ai.bytes().all(|b| b.is_ascii_digit());
+    let query = query.split('&').filter_map(|p| p.split_once('='));
+    let query = query.filter(|(ai, _)| is_ai(ai));
+    path.chain(query)
+        .map(|(ai, value)| {
+            if !is_ai(ai) {
+                return Err(invalid(url));
+            }
+            let value = percent_decode(value).ok_or_else(|| invalid(url))?;
+            Ok((ai.to_string(), value))
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = match b {
+            b'%' => {
+                let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                &tail[2..]
+            },
+            b => {
+                bytes.push(b);
+                tail
+            },
+        };
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Parses `YYMMDD`. The day `00` means the last day of the month. The years
+/// are read as 2000–2099.
+fn parse_date(value: &str) -> Result<NaiveDate, BarcodeError> {
+    let number = |range: std::ops::Range<usize>| {
+        value.get(range).filter(|n| n.bytes().all(|b| b.is_ascii_digit()))?.parse::<u32>().ok()
+    };
+    let date = match (value.len(), number(0..2), number(2..4), number(4..6)) {
+        (6, Some(year), Some(month), Some(0)) => {
+            NaiveDate::from_ymd_opt(2000 + year as i32, month, 1)
+                .and_then(|d| d.checked_add_months(Months::new(1)))
+                .and_then(|d| d.checked_sub_days(Days::new(1)))
+        },
+        (6, Some(year), Some(month), Some(day)) => {
+            NaiveDate::from_ymd_opt(2000 + year as i32, month, day)
+        },
+        _ => None,
+    };
+    date.ok_or_else(|| invalid(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn test_notations() {
+        let gtin = Barcode::ean13("4002674044119").ok();
+        let raw = Gs1Data::parse("]d201040026740441191725063010LOT-7\u{1d}3103001250").unwrap();
+        assert_eq!(raw.gtin, gtin);
+        assert_eq!(raw.expiry, date(2025, 6, 30));
+        assert_eq!(raw.batch.as_deref(), Some("LOT-7"));
+        assert_eq!(raw.net_weight_kg, Some(1.25));
+
+        let human = Gs1Data::parse("(01)04002674044119(17)250630(10)LOT-7(3103)001250").unwrap();
+        assert_eq!(human, raw);
+
+        let url = "https://id.gs1.org/01/04002674044119/10/LOT%2D7?17=250630&3103=001250";
+        assert_eq!(Gs1Data::parse(url).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_dates() {
+        let data = Gs1Data::parse("(01)04002674044119(15)240200(11)231224(99)xyz").unwrap();
+        assert_eq!(data.best_before, date(2024, 2, 29));
+        assert_eq!(data.production_date, date(2023, 12, 24));
+        assert_eq!(data.other, [("99".to_string(), "xyz".to_string())]);
+        assert!(Gs1Data::parse("(01)04002674044119(17)251301").is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Gs1Data::parse("https://example.com").is_err());
+        assert!(Gs1Data::parse("hello world").is_err());
+        assert!(Gs1Data::parse("010400267404411").is_err());
+        assert!(Gs1Data::parse("(01)04002674044118").is_err());
+        assert!(Gs1Data::parse("https://x/01/04002674044119/310!/5").is_err());
+        assert!(Gs1Data::parse("(01)04002674044119(310a)001250").is_err());
+    }
+}
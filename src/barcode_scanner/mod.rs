@@ -1,22 +1,26 @@
 mod barcode;
 mod error;
+mod gs1;
+mod svg;
 
 use crate::{camera::CameraService, option_signal::OptionSignal};
 pub use barcode::*;
 pub use error::BarcodeError;
+pub use gs1::Gs1Data;
 use leptos::{
     html::{Canvas, Video},
     leptos_dom::helpers::IntervalHandle,
     *,
 };
 use rxing::{
-    common::HybridBinarizer, oned::MultiFormatUPCEANReader, BinaryBitmap, DecodingHintDictionary,
-    Luma8LuminanceSource, RXingResult, Reader,
+    common::HybridBinarizer, oned::MultiFormatUPCEANReader, BarcodeFormat, BinaryBitmap,
+    DecodeHintType, DecodeHintValue, DecodingHintDictionary, Luma8LuminanceSource,
+    MultiFormatReader, RXingResult, Reader,
 };
 use rxing_wasm::BarcodeResult;
-use std::{fmt::Debug, time::Duration};
+use std::{cell::RefCell, collections::HashSet, fmt::Debug, time::Duration};
 use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement};
+use web_sys::{js_sys, CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement};
 
 pub fn get_2d_context(
     canvas: &HtmlCanvasElement,
@@ -31,6 +35,51 @@ pub fn get_2d_context(
 
 const NEEDED_SCAN_COUNT: u8 = 3;
 
+/// In the continuous mode, the same code is only reported again after it was
+/// out of view for this long.
+const CONTINUOUS_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// The time the regions of a frame may take to scan. A frame is scanned every
+/// 50ms.
+const FRAME_BUDGET_MS: f64 = 35.0;
+
+/// The height of the bands which are searched for 1D barcodes.
+const BAND_HEIGHT: u32 = 100;
+
+/// The centers of the horizontal bands relative to the video height, the
+/// middle one first.
+const BAND_POSITIONS: [f64; 3] = [0.5, 0.3, 0.7];
+
+/// The square in the middle of the video which is searched for 2D codes is
+/// scaled down to this size.
+const SQUARE_SIZE: u32 = 400;
+
+/// The whole frame is scaled down to this width before it is searched.
+const FULL_FRAME_WIDTH: u32 = 480;
+
+/// A scanned code. DataMatrix and QR codes also carry GS1 data, e.g. the best
+/// before date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scan {
+    pub barcode: Barcode,
+    pub gs1: Option<Gs1Data>,
+}
+
+impl TryFrom<BarcodeResult> for Scan {
+    type Error = BarcodeError;
+
+    fn try_from(value: BarcodeResult) -> Result<Self, Self::Error> {
+        match value.format() {
+            rxing_wasm::BarcodeFormat::DataMatrix | rxing_wasm::BarcodeFormat::QrCode => {
+                let gs1 = Gs1Data::parse(&value.text())?;
+                let barcode = gs1.gtin.ok_or(BarcodeError::MissingGtin)?;
+                Ok(Scan { barcode, gs1: Some(gs1) })
+            },
+            _ => Ok(Scan { barcode: Barcode::try_from(value)?, gs1: None }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum ScanCount {
     Zero,
@@ -44,7 +93,8 @@ impl ScanCount {
         *self = match (*self, next) {
             (_, None) => ScanCount::Zero,
             (ScanCount::Count { barcode: kind, count }, Some(barcode)) if kind == barcode => {
-                ScanCount::Count { barcode: kind, count: count + 1 }
+                // Saturates, because the camera stays open in the continuous mode.
+                ScanCount::Count { barcode: kind, count: count.saturating_add(1) }
             },
             (_, Some(barcode)) => ScanCount::Count { barcode, count: 1 },
         }
@@ -62,63 +112,39 @@ impl ScanCount {
     }
 }
 
-fn scan_for_barcode(
+/// Draws the region `(sx, sy, sw, sh)` of the video scaled to `width` ×
+/// `height` onto the canvas and binarizes it. A `rotated` region is turned by
+/// 90°, so its height runs along the rows of the canvas.
+fn capture(
     video: &HtmlVideoElement,
     canvas: &HtmlCanvasElement,
-) -> Result<Option<Barcode>, BarcodeError> {
-    let v_width_int = video.video_width();
-    let v_height_int = video.video_height();
-    if v_width_int == 0 || v_height_int == 0 {
-        return Ok(None);
-    }
-
-    web_sys::console::time_with_label("scan_for_barcode full");
-    let v_width = v_width_int as f64;
-    /*
-    let v_height = v_height_int as f64;
-    canvas.set_width(v_width_int);
-    canvas.set_height(v_height_int);
-    let context = get_2d_context(&canvas)?;
-
-    time_with_label("draw");
-    context
-        .draw_image_with_html_video_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-            &video, 0.0, 0.0, v_width, v_height, 0.0, 0.0, v_width, v_height,
-        )
-        .map_err(BarcodeError::DrawImgErr)?;
-    time_end_with_label("draw");
-
-    let img_data = context
-        .get_image_data(0.0, 0.0, v_width, v_height)
-        .map_err(BarcodeError::GetImgDataErr)?
-        .data();
-    */
-
-    const CANVAS_HEIGHT: u32 = 100;
-    canvas.set_width(v_width_int);
-    canvas.set_height(CANVAS_HEIGHT);
-    let dy = v_height_int.saturating_sub(CANVAS_HEIGHT).div_euclid(2) as f64;
-
-    let context = get_2d_context(&canvas)?;
+    (sx, sy, sw, sh): (f64, f64, f64, f64),
+    width: u32,
+    height: u32,
+    rotated: bool,
+) -> Result<BinaryBitmap<HybridBinarizer<Luma8LuminanceSource>>, BarcodeError> {
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = get_2d_context(canvas)?;
+
+    let (dw, dh) = if rotated {
+        context.translate(0.0, height as f64).map_err(BarcodeError::DrawImgErr)?;
+        context.rotate(-std::f64::consts::FRAC_PI_2).map_err(BarcodeError::DrawImgErr)?;
+        (height as f64, width as f64)
+    } else {
+        (width as f64, height as f64)
+    };
 
     // time_with_label("draw");
     context
         .draw_image_with_html_video_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-            &video,
-            0.0,
-            dy,
-            v_width,
-            CANVAS_HEIGHT as f64,
-            0.0,
-            0.0,
-            v_width,
-            CANVAS_HEIGHT as f64,
+            video, sx, sy, sw, sh, 0.0, 0.0, dw, dh,
         )
         .map_err(BarcodeError::DrawImgErr)?;
     // time_end_with_label("draw"); // ~ 2ms
 
     let img_data = context
-        .get_image_data(0.0, 0.0, v_width, CANVAS_HEIGHT as f64)
+        .get_image_data(0.0, 0.0, width as f64, height as f64)
         .map_err(BarcodeError::GetImgDataErr)?
         .data();
 
@@ -126,22 +152,169 @@ fn scan_for_barcode(
     let luma_img = rxing_wasm::convert_js_image_to_luma(&img_data);
     // time_end_with_label("luma"); // ~ 10ms
 
-    // time_with_label("decode_time");
-    let luma_img = Luma8LuminanceSource::new(luma_img, v_width_int, CANVAS_HEIGHT);
-    let mut bitmap = BinaryBitmap::new(HybridBinarizer::new(luma_img));
-    let hints = DecodingHintDictionary::new();
-    let barcode = MultiFormatUPCEANReader::new(&hints).decode_with_hints(&mut bitmap, &hints);
+    let luma_img = Luma8LuminanceSource::new(luma_img, width, height);
+    Ok(BinaryBitmap::new(HybridBinarizer::new(luma_img)))
+}
 
-    let barcode = barcode.map(BarcodeResult::from).ok();
+fn decode_1d(
+    mut bitmap: BinaryBitmap<HybridBinarizer<Luma8LuminanceSource>>,
+    hints: &DecodingHintDictionary,
+) -> Result<Option<Scan>, BarcodeError> {
+    // time_with_label("decode_time");
+    let barcode = MultiFormatUPCEANReader::new(hints).decode_with_hints(&mut bitmap, hints);
     // time_end_with_label("decode_time"); // ~ 0.1ms
+    match barcode {
+        Ok(barcode) => Scan::try_from(BarcodeResult::from(barcode)).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Searches the square in the middle of the video for GS1 DataMatrix and QR
+/// codes. Other 2D codes, e.g. links, are ignored.
+fn scan_for_2d_code(
+    video: &HtmlVideoElement,
+    canvas: &HtmlCanvasElement,
+) -> Result<Option<Scan>, BarcodeError> {
+    let (v_width, v_height) = (video.video_width(), video.video_height());
+    let side = v_width.min(v_height);
+    let size = side.min(SQUARE_SIZE);
+    let (sx, sy) = ((v_width - side) / 2, (v_height - side) / 2);
+    let region = (sx as f64, sy as f64, side as f64, side as f64);
+    let mut bitmap = capture(video, canvas, region, size, size, false)?;
+
+    let formats = HashSet::from([BarcodeFormat::DATA_MATRIX, BarcodeFormat::QR_CODE]);
+    let hints = DecodingHintDictionary::from([(
+        DecodeHintType::POSSIBLE_FORMATS,
+        DecodeHintValue::PossibleFormats(formats),
+    )]);
+    let Ok(result) = MultiFormatReader::default().decode_with_hints(&mut bitmap, &hints) else {
+        return Ok(None);
+    };
+    let scan = Scan::try_from(BarcodeResult::from(result))
+        .inspect_err(|err| logging::log!("ignored 2D code: {}", err))
+        .ok();
+    Ok(scan)
+}
+
+/// A part of the video which is searched for codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Region {
+    /// A horizontal band centered at this fraction of the video height.
+    Band(f64),
+    /// The vertical band in the middle, for barcodes which are held upright.
+    Rotated,
+    /// The square in the middle, which is searched for 2D codes.
+    Square,
+    /// The whole frame at a lower resolution. Every row is searched, so it
+    /// also finds small barcodes away from the bands.
+    FullFrame,
+}
+
+impl Region {
+    fn scan(
+        self,
+        video: &HtmlVideoElement,
+        canvas: &HtmlCanvasElement,
+    ) -> Result<Option<Scan>, BarcodeError> {
+        let (width, height) = (video.video_width(), video.video_height());
+        let hints = DecodingHintDictionary::new();
+        match self {
+            Region::Band(center) => {
+                let band = BAND_HEIGHT.min(height);
+                let max_y = (height - band) as f64;
+                let sy = (height as f64 * center - band as f64 / 2.0).clamp(0.0, max_y);
+                let region = (0.0, sy, width as f64, band as f64);
+                decode_1d(capture(video, canvas, region, width, band, false)?, &hints)
+            },
+            Region::Rotated => {
+                let band = BAND_HEIGHT.min(width);
+                let region = ((width - band) as f64 / 2.0, 0.0, band as f64, height as f64);
+                decode_1d(capture(video, canvas, region, height, band, true)?, &hints)
+            },
+            Region::Square => scan_for_2d_code(video, canvas),
+            Region::FullFrame => {
+                let scale = (FULL_FRAME_WIDTH as f64 / width as f64).min(1.0);
+                let (w, h) = ((width as f64 * scale) as u32, (height as f64 * scale) as u32);
+                let region = (0.0, 0.0, width as f64, height as f64);
+                let hints = DecodingHintDictionary::from([(
+                    DecodeHintType::TRY_HARDER,
+                    DecodeHintValue::TryHarder(true),
+                )]);
+                decode_1d(capture(video, canvas, region, w.max(1), h.max(1), false)?, &hints)
+            },
+        }
+    }
+}
+
+/// Chooses the regions which are searched in a frame, so the scan stays
+/// within [`FRAME_BUDGET_MS`]. The region where the last code was found is
+/// searched first. The other regions take turns with the remaining time,
+/// based on how long they took before, so slow devices search fewer regions
+/// per frame but still all of them after a few frames.
+#[derive(Debug, Clone)]
+struct ScanStrategy {
+    /// The regions with their average scan time in ms.
+    regions: Vec<(Region, f64)>,
+    /// The offset of the region after the first one which takes the next turn.
+    next: usize,
+}
+
+impl Default for ScanStrategy {
+    fn default() -> Self {
+        let bands = BAND_POSITIONS.into_iter().map(Region::Band);
+        let regions = bands.chain([Region::Rotated, Region::Square, Region::FullFrame]);
+        ScanStrategy { regions: regions.map(|r| (r, 0.0)).collect(), next: 0 }
+    }
+}
 
-    web_sys::console::time_end_with_label("scan_for_barcode full"); // ~ 30ms
+impl ScanStrategy {
+    fn scan(
+        &mut self,
+        video: &HtmlVideoElement,
+        canvas: &HtmlCanvasElement,
+    ) -> Result<Option<Scan>, BarcodeError> {
+        if video.video_width() == 0 || video.video_height() == 0 {
+            return Ok(None);
+        }
 
-    barcode.map(Barcode::try_from).transpose()
+        let start = js_sys::Date::now();
+        let others = self.regions.len() - 1;
+        let turns = (0..others).map(|i| 1 + (self.next + i) % others);
+        for (turn, idx) in std::iter::once(0).chain(turns).enumerate() {
+            let (region, cost) = self.regions[idx];
+            let elapsed = js_sys::Date::now() - start;
+            // At least one of the other regions takes its turn in each frame.
+            if turn > 1 && elapsed + cost > FRAME_BUDGET_MS {
+                self.next = (self.next + turn - 1) % others;
+                return Ok(None);
+            }
+
+            let scan = region.scan(video, canvas)?;
+            let took = js_sys::Date::now() - start - elapsed;
+            self.regions[idx].1 = if cost == 0.0 { took } else { 0.8 * cost + 0.2 * took };
+
+            if let Some(scan) = scan {
+                let found = self.regions.remove(idx);
+                self.regions.insert(0, found);
+                return Ok(Some(scan));
+            }
+        }
+        Ok(None)
+    }
 }
 
 #[component]
-pub fn BarcodeScanner<F>(set_barcode: F) -> impl IntoView
+pub fn BarcodeScanner<F>(
+    set_barcode: F,
+    /// Keeps scanning after a code was found, e.g. to add many products at
+    /// once.
+    #[prop(optional)]
+    continuous: bool,
+    /// Called with the data of a GS1 DataMatrix or QR code before `set_barcode`
+    /// is called with its GTIN.
+    #[prop(optional, into)]
+    set_gs1_data: Option<Callback<Gs1Data>>,
+) -> impl IntoView
 where F: Fn(Barcode) + Copy + 'static {
     let barcode_scanner_error = OptionSignal::<RwSignal<Option<BarcodeError>>>::new();
     let barcode_count = create_rw_signal(ScanCount::Zero);
@@ -157,6 +330,7 @@ where F: Fn(Barcode) + Copy + 'static {
 
     #[cfg(feature = "hydrate")]
     {
+        let gs1_data = create_rw_signal(None::<Gs1Data>);
         let camera = CameraService::from_context();
         let video_stream = camera.open();
         on_cleanup(move || camera.close());
@@ -170,12 +344,19 @@ where F: Fn(Barcode) + Copy + 'static {
                 .unwrap_or_default()
         };
 
+        let strategy = RefCell::new(ScanStrategy::default());
         let scan_interval = set_interval_with_handle(
             move || {
                 let Some(video) = video() else { return };
                 let Some(canvas) = canvas() else { return };
-                match scan_for_barcode(&video, &canvas) {
-                    Ok(barcode) => barcode_count.update(|c| c.update(barcode)),
+                match strategy.borrow_mut().scan(&video, &canvas) {
+                    Ok(scan) => {
+                        let (barcode, gs1) = scan.map(|s| (s.barcode, s.gs1)).unzip();
+                        if let Some(gs1) = gs1.flatten() {
+                            gs1_data.set(Some(gs1));
+                        }
+                        barcode_count.update(|c| c.update(barcode))
+                    },
                     Err(err) => barcode_scanner_error(err),
                 }
             },
@@ -187,11 +368,25 @@ where F: Fn(Barcode) + Copy + 'static {
             .ok();
         on_cleanup(move || scan_interval.iter().for_each(IntervalHandle::clear));
 
-        create_effect(move |_| match barcode_count() {
-            ScanCount::Count { barcode, count } if count >= NEEDED_SCAN_COUNT => {
-                set_barcode(barcode);
-            },
-            _ => (),
+        // Returns the last reported code and when it was last seen.
+        create_effect(move |last: Option<Option<(Barcode, f64)>>| {
+            let last = last.flatten();
+            match barcode_count() {
+                ScanCount::Count { barcode, count } if count >= NEEDED_SCAN_COUNT => {
+                    let now = js_sys::Date::now();
+                    let debounce = CONTINUOUS_DEBOUNCE.as_millis() as f64;
+                    if continuous && last.is_some_and(|(b, t)| b == barcode && now - t < debounce) {
+                        return Some((barcode, now));
+                    }
+                    let gs1 = gs1_data.get_untracked().filter(|d| d.gtin == Some(barcode));
+                    if let (Some(set_gs1_data), Some(gs1)) = (set_gs1_data, gs1) {
+                        set_gs1_data.call(gs1);
+                    }
+                    set_barcode(barcode);
+                    Some((barcode, now))
+                },
+                _ => last,
+            }
         });
 
         create_effect(move |_| match (video(), video_stream()) {
@@ -212,9 +407,9 @@ where F: Fn(Barcode) + Copy + 'static {
             .unwrap_or_default()
     };
 
-    let manually_set_barcode = move |ev| match Barcode::try_from(event_target_value(&ev)) {
+    let manually_set_barcode = move |ev| match event_target_value(&ev).parse() {
         Ok(barcode) => barcode_count.update(|b| b.set_manually(barcode)),
-        Err(err) => barcode_scanner_error.set(BarcodeError::ParseBarcodeErr(err)),
+        Err(err) => barcode_scanner_error.set(err),
     };
 
     view! {
@@ -227,7 +422,11 @@ where F: Fn(Barcode) + Copy + 'static {
                 fallback=error_view
             >
                 <Transition fallback=move || view! { <p>"Loading..."</p> }>
-                    <video ref_=video playsinline autoplay muted />
+                    <div class="scanner-view">
+                        <video ref_=video playsinline autoplay muted />
+                        <div class="scan-guide" />
+                    </div>
+                    <p class="scan-hint">"Hold the code inside the frame, across or upright"</p>
                 </Transition>
                 <canvas ref_=canvas hidden />
             </Show>
@@ -241,3 +440,18 @@ where F: Fn(Barcode) + Copy + 'static {
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_count_saturates() {
+        let barcode = "4002674044119".parse::<Barcode>().unwrap();
+        let mut scan_count = ScanCount::Zero;
+        for _ in 0..300 {
+            scan_count.update(Some(barcode));
+        }
+        assert!(matches!(scan_count, ScanCount::Count { count: u8::MAX, .. }));
+    }
+}
@@ -0,0 +1,189 @@
+//! Renders barcodes as SVG images, so they can be scanned from the screen,
+//! e.g. at a self-checkout.
+
+use super::{Barcode, BarcodeType};
+use std::fmt::Write;
+
+/// The height of the bars in modules (the width of the thinnest bar).
+const BAR_HEIGHT: usize = 55;
+/// The start, middle and end guards are longer.
+const GUARD_HEIGHT: usize = 60;
+const TEXT_Y: usize = 64;
+const HEIGHT: usize = 66;
+
+/// The patterns of the digits in the L (odd parity) set. The R set is their
+/// complement and the G (even parity) set the reversed complement.
+const L_CODES: [u8; 10] = [
+    0b0001101, 0b0011001, 0b0010011, 0b0111101, 0b0100011, 0b0110001, 0b0101111, 0b0111011,
+    0b0110111, 0b0001011,
+];
+
+/// The sets of the left half of an EAN-13 code, selected by its first digit.
+/// A set bit means G, starting with the second digit at bit 5.
+const EAN13_PARITY: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110,
+    0b011010,
+];
+
+/// The sets of a UPC-E code with number system 0, selected by its check
+/// digit. A set bit means G. Number system 1 uses the complement.
+const UPC_E_PARITY: [u8; 10] = [
+    0b111000, 0b110100, 0b110010, 0b110001, 0b101100, 0b100110, 0b100011, 0b101010, 0b101001,
+    0b100101,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Module {
+    Space,
+    Bar,
+    Guard,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Set {
+    L,
+    G,
+    R,
+}
+
+#[derive(Debug, Default)]
+struct Symbol {
+    modules: Vec<Module>,
+    /// The human readable digits with their horizontal center in modules.
+    text: Vec<(f64, u8)>,
+}
+
+impl Symbol {
+    fn guard(&mut self, pattern: &str) {
+        let module = |b| if b == b'1' { Module::Guard } else { Module::Space };
+        self.modules.extend(pattern.bytes().map(module));
+    }
+
+    fn digit(&mut self, digit: u8, set: Set) {
+        let code = match set {
+            Set::L => L_CODES[digit as usize],
+            Set::R => !L_CODES[digit as usize] & 0x7f,
+            Set::G => (!L_CODES[digit as usize] & 0x7f).reverse_bits() >> 1,
+        };
+        self.text.push((self.modules.len() as f64 + 3.5, digit));
+        let module = |i: u8| if code >> i & 1 == 1 { Module::Bar } else { Module::Space };
+        self.modules.extend((0..7).rev().map(module));
+    }
+
+    /// Digits outside of the bars, e.g. the first digit of an EAN-13 code.
+    fn outer_digit(&mut self, x: f64, digit: u8) {
+        self.text.push((x, digit));
+    }
+
+    fn ean13(digits: &[u8]) -> Symbol {
+        let mut symbol = Symbol::default();
+        symbol.outer_digit(-5.0, digits[0]);
+        symbol.guard("101");
+        for (i, &d) in digits[1..7].iter().enumerate() {
+            let g = EAN13_PARITY[digits[0] as usize] >> (5 - i) & 1 == 1;
+            symbol.digit(d, if g { Set::G } else { Set::L });
+        }
+        symbol.guard("01010");
+        digits[7..].iter().for_each(|&d| symbol.digit(d, Set::R));
+        symbol.guard("101");
+        symbol
+    }
+
+    fn ean8(digits: &[u8]) -> Symbol {
+        let mut symbol = Symbol::default();
+        symbol.guard("101");
+        digits[..4].iter().for_each(|&d| symbol.digit(d, Set::L));
+        symbol.guard("01010");
+        digits[4..].iter().for_each(|&d| symbol.digit(d, Set::R));
+        symbol.guard("101");
+        symbol
+    }
+
+    fn upc_e(digits: &[u8]) -> Symbol {
+        let mut symbol = Symbol::default();
+        let parity = match digits[0] {
+            0 => UPC_E_PARITY[digits[7] as usize],
+            _ => !UPC_E_PARITY[digits[7] as usize] & 0b111111,
+        };
+        symbol.outer_digit(-5.0, digits[0]);
+        symbol.guard("101");
+        for (i, &d) in digits[1..7].iter().enumerate() {
+            symbol.digit(d, if parity >> (5 - i) & 1 == 1 { Set::G } else { Set::L });
+        }
+        symbol.guard("010101");
+        symbol.outer_digit(symbol.modules.len() as f64 + 5.0, digits[7]);
+        symbol
+    }
+
+    fn to_svg(&self) -> String {
+        // The quiet zones also hold the outer digits.
+        let (left, right) = (11, 9);
+        let width = left + self.modules.len() + right;
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {HEIGHT}""#
+        );
+        svg.push_str(r#" font-family="monospace" font-size="9" text-anchor="middle">"#);
+        let _ = write!(svg, r#"<rect width="{width}" height="{HEIGHT}" fill="white"/>"#);
+        let mut x = 0;
+        while x < self.modules.len() {
+            let module = self.modules[x];
+            let start = x;
+            while x < self.modules.len() && self.modules[x] == module {
+                x += 1;
+            }
+            let height = match module {
+                Module::Space => continue,
+                Module::Bar => BAR_HEIGHT,
+                Module::Guard => GUARD_HEIGHT,
+            };
+            let _ = write!(
+                svg,
+                r#"<rect x="{}" width="{}" height="{height}"/>"#,
+                left + start,
+                x - start
+            );
+        }
+        for (x, digit) in &self.text {
+            let _ = write!(svg, r#"<text x="{}" y="{TEXT_Y}">{digit}</text>"#, left as f64 + x);
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+impl Barcode {
+    /// Renders the barcode in its own symbology. UPC-A codes are rendered as
+    /// EAN-13, which has the same bars, and GTINs with the EAN-8 prefix as
+    /// EAN-8. GTIN-14 codes of outer packaging can't be rendered.
+    pub fn to_svg(&self) -> Option<String> {
+        let digits = |s: String| s.bytes().map(|b| b - b'0').collect::<Vec<_>>();
+        let symbol = match (self.get_type(), self.ean8_digits()) {
+            (BarcodeType::UpcE, _) => Symbol::upc_e(&digits(self.get_digits())),
+            (_, Some(ean8)) => Symbol::ean8(&digits(ean8)),
+            _ => match digits(self.gtin()) {
+                gtin if gtin.len() == 13 => Symbol::ean13(&gtin),
+                _ => return None,
+            },
+        };
+        Some(symbol.to_svg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modules() {
+        let count = |svg: Option<String>| svg.unwrap().matches("<rect x=").count();
+        // 2 bars per digit and per guard.
+        assert_eq!(count(Barcode::ean13("4002674044119").ok().and_then(|b| b.to_svg())), 30);
+        assert_eq!(count(Barcode::ean8("96385074").ok().and_then(|b| b.to_svg())), 22);
+        assert_eq!(count("04252614".parse::<Barcode>().ok().and_then(|b| b.to_svg())), 17);
+        assert!("14002674044116".parse::<Barcode>().unwrap().to_svg().is_none());
+
+        let symbol = Symbol::ean13(&[4, 0, 0, 2, 6, 7, 4, 0, 4, 4, 1, 1, 9]);
+        assert_eq!(symbol.modules.len(), 95);
+        assert_eq!(Symbol::upc_e(&[0, 4, 2, 5, 2, 6, 1, 4]).modules.len(), 51);
+    }
+}
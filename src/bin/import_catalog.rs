@@ -0,0 +1,70 @@
+//! Imports an OpenFoodFacts data dump into the local catalog:
+//!
+//! ```sh
+//! zcat openfoodfacts-products.jsonl.gz | import_catalog --country germany -
+//! import_catalog --country en:austria en.openfoodfacts.org.products.csv
+//! ```
+//!
+//! The database is selected with `DATABASE_URL` like for the server.
+
+use shopping_list::{
+    catalog::{self, Format, ImportOptions},
+    db::MY_DB,
+};
+use std::io::{BufRead, BufReader};
+
+const USAGE: &str = "usage: import_catalog [--country <tag>]... [--format jsonl|csv] <file|->";
+
+fn exit_with(msg: impl std::fmt::Display) -> ! {
+    eprintln!("{msg}\n{USAGE}");
+    std::process::exit(1)
+}
+
+#[tokio::main]
+async fn main() {
+    let mut options = ImportOptions::default();
+    let mut format = None;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--country" => match args.next() {
+                Some(country) => options.add_country(&country),
+                None => exit_with("missing country"),
+            },
+            "--format" => match args.next().as_deref().map(Format::try_from) {
+                Some(Ok(f)) => format = Some(f),
+                Some(Err(err)) => exit_with(err),
+                None => exit_with("missing format"),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            },
+            _ if path.is_none() => path = Some(arg),
+            _ => exit_with(format!("unexpected argument: {arg:?}")),
+        }
+    }
+    let Some(path) = path else { exit_with("missing file") };
+
+    let format = format.unwrap_or_else(|| Format::from_path(&path));
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        match std::fs::File::open(&path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => exit_with(format!("can't open {path:?}: {err}")),
+        }
+    };
+
+    match catalog::import(reader, format, &options, &MY_DB).await {
+        Ok(stats) => println!(
+            "imported {} products ({} in other countries, {} invalid)",
+            stats.imported, stats.filtered, stats.invalid
+        ),
+        Err(err) => {
+            eprintln!("ERROR: {err}");
+            std::process::exit(1);
+        },
+    }
+}
@@ -1,7 +1,11 @@
 use crate::util::{force_use_context, IntoJsFuture, JsSet, JsSetError};
 use leptos::*;
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{js_sys::Object, MediaStream, MediaStreamConstraints, MediaStreamTrack};
+use web_sys::{
+    js_sys::{Object, Promise},
+    CanvasRenderingContext2d, File, HtmlCanvasElement, HtmlImageElement, MediaStream,
+    MediaStreamConstraints, MediaStreamTrack, Url,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct CameraService {
@@ -80,6 +84,33 @@ impl CameraService {
     }
 }
 
+/// Loads the image `file`, e.g. from an `<input type="file">`, and returns it
+/// as a JPEG `data:` URL which is at most `max_size` pixels wide and high.
+pub async fn photo_data_url(file: File, max_size: u32) -> Result<String, JsValue> {
+    let url = Url::create_object_url_with_blob(&file)?;
+    let img = HtmlImageElement::new()?;
+    let loaded = Promise::new(&mut |resolve, reject| {
+        img.set_onload(Some(&resolve));
+        img.set_onerror(Some(&reject));
+    });
+    img.set_src(&url);
+    let loaded = loaded.into_future().await;
+    Url::revoke_object_url(&url)?;
+    loaded?;
+
+    let (width, height) = (img.natural_width(), img.natural_height());
+    let scale = (max_size as f64 / width.max(height).max(1) as f64).min(1.0);
+    let (width, height) = ((width as f64 * scale).round(), (height as f64 * scale).round());
+
+    let canvas: HtmlCanvasElement = document().create_element("canvas")?.unchecked_into();
+    canvas.set_width(width as u32);
+    canvas.set_height(height as u32);
+    let context: CanvasRenderingContext2d =
+        canvas.get_context("2d")?.ok_or("no 2d context")?.unchecked_into();
+    context.draw_image_with_html_image_element_and_dw_and_dh(&img, 0.0, 0.0, width, height)?;
+    canvas.to_data_url_with_type("image/jpeg")
+}
+
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum CameraError {
     #[error("can't access camera")]
@@ -14,9 +14,14 @@ lazy_static::lazy_static! {
     pub static ref MY_DB: DB = DB::new().expect("could connect to DB");
 }
 
+/// The `DATABASE_URL` environment variable overrides the URL from `.env`.
+fn db_url() -> String {
+    std::env::var("DATABASE_URL").unwrap_or_else(|_| DB_URL.to_string())
+}
+
 impl DB {
     pub fn new() -> Result<DB> {
-        let pool = Pool::connect_lazy(DB_URL)?;
+        let pool = Pool::connect_lazy(&db_url())?;
         Ok(DB { pool })
     }
 
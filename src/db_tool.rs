@@ -1,22 +1,44 @@
 use crate::{
     barcode_scanner::Barcode,
-    item::{data::NewItem, openfoodsfacts, server_functions::add_item_from_barcode},
+    item::{
+        data::NewItem,
+        openfoodsfacts::{self, get_cache_stats},
+        server_functions::add_item_from_barcode,
+    },
+    language::Language,
 };
 use leptos::*;
 use leptos_router::*;
 
+/// `refresh` is set if the "bypass cache" checkbox is checked. `lang` is a
+/// language code.
 #[server]
-pub async fn db_action(barcode: String, action: String) -> Result<String, ServerFnError> {
-    let barcode = Barcode::try_from(barcode)?;
+pub async fn db_action(
+    barcode: String,
+    action: String,
+    refresh: Option<String>,
+    lang: String,
+) -> Result<String, ServerFnError> {
+    if action == "clear cache" {
+        let removed = openfoodsfacts::cache::clear(&crate::db::MY_DB).await?;
+        return Ok(format!("Removed {removed} cache entries"));
+    }
+
+    let barcode = barcode.parse::<Barcode>()?;
+    let refresh = refresh.is_some();
+    let lang = Language::new(lang).unwrap_or_default();
 
     match action.as_str() {
-        "request json" => Ok(format!("{:#}", openfoodsfacts::request_with_barcode(barcode).await?)),
+        "request json" => Ok(format!(
+            "{:#}",
+            crate::item::provider::request_with_barcode(barcode, refresh, &crate::db::MY_DB).await?
+        )),
         "request OpenFoodFactsProduct" => Ok(format!(
             "{:#?}",
-            openfoodsfacts::OpenFoodFactsProduct::request_with_barcode(barcode).await?
+            openfoodsfacts::OpenFoodFactsProduct::lookup(barcode, refresh, lang).await?
         )),
-        "request ItemData" => Ok(format!("{:#?}", NewItem::from_barcode(barcode).await?)),
-        "Add Item" => add_item_from_barcode(barcode).await.map(|_| format!("Added Item")),
+        "request ItemData" => Ok(format!("{:#?}", NewItem::from_barcode(barcode, lang).await?)),
+        "Add Item" => add_item_from_barcode(barcode, lang).await.map(|_| format!("Added Item")),
         _ => Err(ServerFnError::new(format!("invalid action: {:?}", action))),
     }
 }
@@ -30,17 +52,43 @@ pub fn DBTool() -> impl IntoView {
         Err(err) => format!("ERROR: {err}"),
     };
 
+    let stats = create_resource(action.version(), |_| get_cache_stats());
+    let stats_text = move || match stats() {
+        Some(Ok(s)) => format!(
+            "cache: {} hits, {} misses, {} entries ({} not found)",
+            s.hits, s.misses, s.entries, s.not_found_entries
+        ),
+        Some(Err(err)) => format!("ERROR: {err}"),
+        None => "Loading...".to_string(),
+    };
+
     view! {
         <h1>"DB Tool"</h1>
         <ActionForm action=action>
             <label for="barcode-input">"barcode: "</label>
             <input type="text" id="barcode-input" name="barcode"/>
+            <label>
+                <input type="checkbox" name="refresh"/>
+                "bypass cache"
+            </label>
+            <select name="lang" title="Language">
+                {
+                    Language::ALL
+                        .into_iter()
+                        .map(|l| view! { <option value=l.code()>{ l.code() }</option> })
+                        .collect_view()
+                }
+            </select>
             <br/>
             <input type="submit" name="action" value="request json"/>
             <input type="submit" name="action" value="request OpenFoodFactsProduct"/>
             <input type="submit" name="action" value="request ItemData"/>
             <input type="submit" name="action" value="Add Item"/>
+            <input type="submit" name="action" value="clear cache"/>
         </ActionForm>
+        <p class="cache-stats">
+            <Transition fallback=move || view! { "Loading..." }>{ stats_text }</Transition>
+        </p>
         <br/>
         <textarea
             prop:value=text
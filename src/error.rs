@@ -9,6 +9,9 @@ pub enum Error {
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
 
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     #[error(transparent)]
     ServerFn(#[from] leptos::ServerFnErrorErr),
 
@@ -16,12 +19,43 @@ pub enum Error {
     #[error(transparent)]
     DB(#[from] sqlx::Error),
 
+    #[cfg(feature = "ssr")]
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+
     #[error("didn't find product")]
     DidntFindProduct,
 
+    #[error("too many requests to the product database")]
+    RateLimited,
+
+    #[error("the product database took too long to answer")]
+    Timeout,
+
+    #[error("couldn't reach the product database")]
+    Network,
+
+    #[error("the product database is unavailable (HTTP {})", .0)]
+    ServiceUnavailable(u16),
+
+    #[error("unexpected response from the product database (HTTP {})", .0)]
+    UnexpectedStatus(u16),
+
+    #[error("OpenFoodFacts rejected the contribution: {}", .0)]
+    ContributionRejected(String),
+
+    #[error("no OpenFoodFacts account is configured")]
+    ContributionsDisabled,
+
+    #[error("the photo isn't an image data URL")]
+    InvalidPhoto,
+
     #[error("missing \"{}\" field on product", .0)]
     MissingProductField(&'static str),
 
+    #[error("invalid value in database: {}", .0)]
+    InvalidDBValue(String),
+
     #[error("missing context: {}", .0)]
     MissingContext(&'static str),
 
@@ -35,6 +69,14 @@ impl Error {
     pub fn missing_ctx<Ctx>() -> Self {
         Self::MissingContext(any::type_name::<Ctx>())
     }
+
+    /// Whether retrying the request later might succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::RateLimited | Error::Timeout | Error::Network | Error::ServiceUnavailable(_)
+        )
+    }
 }
 
 #[cfg(feature = "ssr")]
@@ -1,5 +1,8 @@
 use crate::{
     item::{RefreshList, ShowNewItem},
+    popup::{Popup, PopupSignal},
+    scan::{ContinuousScanView, ScanMode},
+    template::TemplatesView,
     util::force_use_context,
 };
 use leptos::*;
@@ -22,6 +25,10 @@ pub fn HeaderBar() -> impl IntoView {
 
     let tooltip = move || if show_new_item.0.get() { "Discard new Item" } else { "Add new Item" };
 
+    let templates_popup = PopupSignal::new();
+    let scan_popup = PopupSignal::new();
+    let check_off_popup = PopupSignal::new();
+
     view! {
         <header id="header-bar">
             <div class="header-bar--left">
@@ -32,6 +39,51 @@ pub fn HeaderBar() -> impl IntoView {
                     class="refresh-button cursor-pointer"
                     on:click=move |_| refresh_list.notify()
                 />
+                <img
+                    src="img/template.svg"
+                    alt="Templates"
+                    title="Templates"
+                    class="templates-button cursor-pointer"
+                    on:click=move |_| templates_popup.open()
+                />
+                <img
+                    src="img/barcode-scan-svgrepo-com.svg"
+                    alt="Scan Items"
+                    title="Scan Items"
+                    class="scan-button cursor-pointer"
+                    on:click=move |_| scan_popup.open()
+                />
+                <img
+                    src="img/shopping-cart-svgrepo-com.svg"
+                    alt="Check off Items"
+                    title="Check off Items"
+                    class="check-off-button cursor-pointer"
+                    on:click=move |_| check_off_popup.open()
+                />
+                <a href="/recipes">
+                    <img
+                        src="img/recipe.svg"
+                        alt="Recipes"
+                        title="Recipes"
+                        class="recipes-button cursor-pointer"
+                    />
+                </a>
+                <a href="/recurring">
+                    <img
+                        src="img/repeat.svg"
+                        alt="Recurring Items"
+                        title="Recurring Items"
+                        class="recurring-button cursor-pointer"
+                    />
+                </a>
+                <a href="/profiles">
+                    <img
+                        src="img/profile.svg"
+                        alt="Profiles"
+                        title="Profiles"
+                        class="profiles-button cursor-pointer"
+                    />
+                </a>
             </div>
             <div class="header-bar--center">
                 <h2>"Shopping List"</h2>
@@ -57,5 +109,14 @@ pub fn HeaderBar() -> impl IntoView {
                 />
             </div>
         </header>
+        <Popup popup=templates_popup>
+            <TemplatesView popup=templates_popup />
+        </Popup>
+        <Popup popup=scan_popup>
+            <ContinuousScanView mode=ScanMode::Add />
+        </Popup>
+        <Popup popup=check_off_popup>
+            <ContinuousScanView mode=ScanMode::CheckOff />
+        </Popup>
     }
 }
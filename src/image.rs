@@ -1,19 +1,37 @@
-use crate::popup::{Popup, PopupSignal};
+use crate::{
+    image_store::thumb_srcset,
+    popup::{Popup, PopupSignal},
+};
 use leptos::*;
 
+/// Shown if a variant has no image or it can't be loaded.
+const PLACEHOLDER: &str = "img/image-placeholder.svg";
+
 #[component]
 pub fn Image(
     #[prop(optional_no_strip)] full_url: Option<String>,
     #[prop(optional_no_strip)] thumb_url: Option<String>,
 ) -> impl IntoView {
     let popup = PopupSignal::new();
+    let full_url = full_url.filter(|u| !u.is_empty());
+    let thumb_url = thumb_url.filter(|u| !u.is_empty());
+    let failed = create_rw_signal(false);
+    let srcset = thumb_url.as_deref().and_then(thumb_srcset);
+    let src = thumb_url.clone().or_else(|| full_url.clone());
+    let src = move || match src.clone() {
+        Some(url) if !failed() => url,
+        _ => PLACEHOLDER.to_string(),
+    };
+    let srcset = move || srcset.clone().filter(|_| !failed());
     if let Some(full_url) = full_url.or_else(|| thumb_url.clone()) {
         view! {
             <img
-                src=thumb_url
+                src=src
+                srcset=srcset
                 alt="Item image"
                 title="Full image"
                 class="image cursor-pointer"
+                on:error=move |_| failed.set(true)
                 on:click=move |ev| {
                     ev.stop_propagation();
                     popup.open();
@@ -26,7 +44,7 @@ pub fn Image(
         .into_view()
     } else {
         view! {
-            <img src=thumb_url /*alt="Item image"*/ class="image" />
+            <img src=PLACEHOLDER alt="No image" class="image placeholder" />
         }
         .into_view()
     }
@@ -0,0 +1,203 @@
+//! Copies of the product images on the server, so the list works offline and
+//! phones only download small thumbnails.
+//!
+//! [`run_image_worker`] fetches the images of all variants whose URLs still
+//! point to another origin, stores them with thumbnails in the directory of
+//! the `IMAGE_DIR` environment variable (default: `data/images`) and rewrites
+//! the URLs to [`ROUTE`].
+
+#[cfg(feature = "ssr")]
+use crate::{
+    db::DB,
+    error::{Error, Result},
+    item::openfoodsfacts::client::{classify, CLIENT},
+};
+#[cfg(feature = "ssr")]
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+#[cfg(feature = "ssr")]
+use leptos::logging;
+#[cfg(feature = "ssr")]
+use std::{path::PathBuf, time::Duration};
+
+/// The images are served from `/images/<key>.jpg` and the thumbnails from
+/// `/images/<key>-<width>.jpg`.
+pub const ROUTE: &str = "/images";
+
+/// The widths of the stored thumbnails. The first one is used as `thumb_url`,
+/// the second one for high-density screens.
+const THUMB_SIZES: [u32; 2] = [100, 200];
+
+/// Larger images are scaled down.
+#[cfg(feature = "ssr")]
+const FULL_SIZE: u32 = 1200;
+
+/// How often the worker looks for remote images if it isn't woken up.
+#[cfg(feature = "ssr")]
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The stored images never change, because their key is derived from the
+/// source URL.
+#[cfg(feature = "ssr")]
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+#[cfg(feature = "ssr")]
+lazy_static::lazy_static! {
+    static ref IMAGE_DIR: PathBuf =
+        std::env::var("IMAGE_DIR").unwrap_or_else(|_| "data/images".to_string()).into();
+    static ref WAKE: tokio::sync::Notify = tokio::sync::Notify::new();
+}
+
+/// Returns a `srcset` with all thumbnail sizes if `thumb_url` is a stored
+/// thumbnail.
+pub fn thumb_srcset(thumb_url: &str) -> Option<String> {
+    let key = thumb_url
+        .strip_prefix(ROUTE)?
+        .strip_prefix('/')?
+        .strip_suffix(&format!("-{}.jpg", THUMB_SIZES[0]))?;
+    let sizes = THUMB_SIZES.iter().map(|w| (w, w / THUMB_SIZES[0]));
+    Some(sizes.map(|(w, x)| format!("{ROUTE}/{key}-{w}.jpg {x}x")).collect::<Vec<_>>().join(", "))
+}
+
+/// The FNV-1a hash of the URL as hex.
+#[cfg(feature = "ssr")]
+fn key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325_u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(feature = "ssr")]
+fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Writes the scaled down image and its thumbnails as JPEGs.
+#[cfg(feature = "ssr")]
+fn write_image(key: &str, bytes: &[u8]) -> Result<()> {
+    use image::{imageops::FilterType, ImageFormat};
+
+    std::fs::create_dir_all(&*IMAGE_DIR)?;
+    let image = image::load_from_memory(bytes)?;
+    let image = if image.width().max(image.height()) > FULL_SIZE {
+        image.resize(FULL_SIZE, FULL_SIZE, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let thumbs = THUMB_SIZES.map(|w| (format!("{key}-{w}.jpg"), image.thumbnail(w, w)));
+    // The full image is written last, so its existence means everything is stored.
+    for (file, image) in thumbs.into_iter().chain([(format!("{key}.jpg"), image)]) {
+        image.to_rgb8().save_with_format(IMAGE_DIR.join(file), ImageFormat::Jpeg)?;
+    }
+    Ok(())
+}
+
+/// Fetches and stores the image at `url` unless it is already stored. Returns
+/// the local URLs of the full image and of the thumbnail.
+#[cfg(feature = "ssr")]
+pub async fn store(url: &str) -> Result<(String, String)> {
+    let key = key(url);
+    let urls = (format!("{ROUTE}/{key}.jpg"), format!("{ROUTE}/{key}-{}.jpg", THUMB_SIZES[0]));
+    if IMAGE_DIR.join(format!("{key}.jpg")).exists() {
+        return Ok(urls);
+    }
+
+    let res = CLIENT.get(url).send().await.map_err(classify)?;
+    if !res.status().is_success() {
+        return Err(Error::UnexpectedStatus(res.status().as_u16()));
+    }
+    let bytes = res.bytes().await.map_err(classify)?;
+    tokio::task::spawn_blocking(move || write_image(&key, &bytes))
+        .await
+        .map_err(|err| Error::Io(std::io::Error::other(err)))??;
+    Ok(urls)
+}
+
+/// Stores the remote images of all variants and rewrites their URLs. Images
+/// which don't exist anymore or can't be decoded are removed from the
+/// variants, so they aren't downloaded again. Returns the number of rewritten
+/// variants.
+#[cfg(feature = "ssr")]
+pub async fn store_remote_images(db: &DB) -> Result<usize> {
+    let rows = {
+        let mut conn = db.connection().await?;
+        sqlx::query!(
+            "SELECT id, img_url, thumb_url FROM item_variant
+            WHERE img_url LIKE 'http%' OR thumb_url LIKE 'http%'"
+        )
+        .fetch_all(conn.as_mut())
+        .await?
+    };
+
+    let mut stored = 0;
+    for row in rows {
+        let Some(url) = row.img_url.filter(|u| is_remote(u)).or(row.thumb_url) else { continue };
+        let (img_url, thumb_url) = match store(&url).await {
+            Ok((img_url, thumb_url)) => (Some(img_url), Some(thumb_url)),
+            // Retried in the next run. Io errors are about the local image directory.
+            Err(err) if err.is_transient() || matches!(err, Error::Io(_)) => {
+                logging::error!("ERROR while storing the image {}: {}", url, err);
+                continue;
+            },
+            Err(err) => {
+                logging::warn!("removing the image of variant {} ({}): {}", row.id, url, err);
+                (None, None)
+            },
+        };
+        let mut conn = db.connection().await?;
+        sqlx::query!(
+            "UPDATE item_variant SET img_url = ?, thumb_url = ? WHERE id = ?",
+            img_url,
+            thumb_url,
+            row.id
+        )
+        .execute(conn.as_mut())
+        .await?;
+        stored += 1;
+    }
+    Ok(stored)
+}
+
+/// Makes [`run_image_worker`] look for remote images now, e.g. after a variant
+/// was added.
+#[cfg(feature = "ssr")]
+pub fn wake() {
+    WAKE.notify_one();
+}
+
+/// Stores the remote images whenever [`wake`] is called and periodically, so
+/// images which couldn't be fetched while offline are retried.
+#[cfg(feature = "ssr")]
+pub async fn run_image_worker() {
+    loop {
+        match store_remote_images(&crate::db::MY_DB).await {
+            Ok(0) => (),
+            Ok(n) => logging::log!("stored the images of {n} variants"),
+            Err(err) => logging::error!("ERROR (image worker): {}", err),
+        }
+        let _ = tokio::time::timeout(CHECK_INTERVAL, WAKE.notified()).await;
+    }
+}
+
+/// Serves the stored images with long cache headers.
+#[cfg(feature = "ssr")]
+pub async fn serve_image(Path(file): Path<String>) -> Response {
+    let valid = file
+        .strip_suffix(".jpg")
+        .is_some_and(|name| name.chars().all(|c| c.is_ascii_hexdigit() || c == '-'));
+    if !valid {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match tokio::fs::read(IMAGE_DIR.join(&file)).await {
+        Ok(bytes) => {
+            let headers =
+                [(header::CONTENT_TYPE, "image/jpeg"), (header::CACHE_CONTROL, CACHE_CONTROL)];
+            (headers, bytes).into_response()
+        },
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
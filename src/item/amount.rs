@@ -0,0 +1,238 @@
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unit {
+    #[default]
+    Pieces,
+    Gram,
+    Kilogram,
+    Millilitre,
+    Litre,
+    Packs,
+}
+
+impl Unit {
+    pub const ALL: [Unit; 6] =
+        [Unit::Pieces, Unit::Gram, Unit::Kilogram, Unit::Millilitre, Unit::Litre, Unit::Packs];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unit::Pieces => "pcs",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Millilitre => "ml",
+            Unit::Litre => "l",
+            Unit::Packs => "packs",
+        }
+    }
+
+    /// The step size used by the `+`/`-` buttons.
+    pub fn step(self) -> f64 {
+        match self {
+            Unit::Pieces | Unit::Packs => 1.0,
+            Unit::Gram | Unit::Millilitre => 50.0,
+            Unit::Kilogram | Unit::Litre => 0.5,
+        }
+    }
+
+    /// Returns the base unit and how many base units are one `self`.
+    fn base(self) -> (Unit, f64) {
+        match self {
+            Unit::Kilogram => (Unit::Gram, 1000.0),
+            Unit::Litre => (Unit::Millilitre, 1000.0),
+            unit => (unit, 1.0),
+        }
+    }
+
+    pub fn is_compatible(self, other: Unit) -> bool {
+        self.base().0 == other.base().0
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Unit {
+    type Error = &'a str;
+
+    fn try_from(unit: &'a str) -> Result<Self, Self::Error> {
+        match unit.trim().to_lowercase().as_str() {
+            "" | "pcs" | "pc" | "x" => Ok(Unit::Pieces),
+            "g" | "gr" => Ok(Unit::Gram),
+            "kg" => Ok(Unit::Kilogram),
+            "ml" => Ok(Unit::Millilitre),
+            "l" => Ok(Unit::Litre),
+            "packs" | "pack" | "pkg" => Ok(Unit::Packs),
+            _ => Err(unit),
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An amount of something, like "1.5 kg" or "3" (pieces).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Amount {
+    /// never negative or NaN
+    value: f64,
+    pub unit: Unit,
+}
+
+impl Eq for Amount {}
+
+impl Default for Amount {
+    fn default() -> Self {
+        Amount::pieces(1.0)
+    }
+}
+
+impl Amount {
+    /// `value` is rounded to three decimal places. Negative values and NaN are
+    /// replaced by zero.
+    pub fn new(value: f64, unit: Unit) -> Amount {
+        let value = if value.is_nan() { 0.0 } else { (value.max(0.0) * 1000.0).round() / 1000.0 };
+        Amount { value, unit }
+    }
+
+    pub fn pieces(value: f64) -> Amount {
+        Amount::new(value, Unit::Pieces)
+    }
+
+    pub fn value(self) -> f64 {
+        self.value
+    }
+
+    pub fn with_value(self, value: f64) -> Amount {
+        Amount::new(value, self.unit)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.value == 0.0
+    }
+
+    /// Returns `None` if the units aren't compatible.
+    pub fn convert_to(self, unit: Unit) -> Option<Amount> {
+        let (from_base, from_factor) = self.unit.base();
+        let (to_base, to_factor) = unit.base();
+        (from_base == to_base).then(|| Amount::new(self.value * from_factor / to_factor, unit))
+    }
+
+    /// Adds `other` in the unit of `self`. Returns `None` if the units aren't
+    /// compatible.
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        other.convert_to(self.unit).map(|other| self.with_value(self.value + other.value))
+    }
+
+    pub fn step_up(self) -> Amount {
+        self.with_value(self.value + self.unit.step())
+    }
+
+    pub fn step_down(self) -> Amount {
+        self.with_value(self.value - self.unit.step())
+    }
+
+    /// Scales the amount by `factor`. Countable units are rounded up.
+    pub fn scale(self, factor: f64) -> Amount {
+        let value = self.value * factor;
+        match self.unit {
+            Unit::Pieces | Unit::Packs => self.with_value((value - 0.001).ceil()),
+            _ => self.with_value(value),
+        }
+    }
+
+    /// Parses amounts like "500 g", "1,5kg" or "2".
+    pub fn parse(text: &str) -> Option<Amount> {
+        let text = text.trim();
+        let unit_start =
+            text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ',')).unwrap_or(text.len());
+        let (value, unit) = text.split_at(unit_start);
+        let value = value.replace(',', ".").parse::<f64>().ok()?;
+        let unit = Unit::try_from(unit).ok()?;
+        Some(Amount::new(value, unit))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            Unit::Pieces => write!(f, "{}", self.value),
+            unit => write!(f, "{} {}", self.value, unit),
+        }
+    }
+}
+
+/// Also accepts a plain number of pieces, which is how amounts were stored
+/// before they had a unit.
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct AmountFields {
+            value: f64,
+            #[serde(default)]
+            unit: Unit,
+        }
+
+        struct AmountVisitor;
+
+        impl<'de> Visitor<'de> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount or a number of pieces")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(Amount::pieces(v as f64))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(Amount::pieces(v as f64))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(Amount::pieces(v))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let AmountFields { value, unit } =
+                    AmountFields::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(Amount::new(value, unit))
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Amount, Unit};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Amount::parse("500 g"), Some(Amount::new(500.0, Unit::Gram)));
+        assert_eq!(Amount::parse("1,5kg"), Some(Amount::new(1.5, Unit::Kilogram)));
+        assert_eq!(Amount::parse("2"), Some(Amount::pieces(2.0)));
+        assert_eq!(Amount::parse("a lot"), None);
+    }
+
+    #[test]
+    fn test_checked_add() {
+        let kg = Amount::new(1.5, Unit::Kilogram);
+        assert_eq!(kg.checked_add(Amount::new(250.0, Unit::Gram)), Some(kg.with_value(1.75)));
+        assert_eq!(kg.checked_add(Amount::new(1.0, Unit::Litre)), None);
+        assert_eq!(Amount::pieces(1.0).checked_add(Amount::new(1.0, Unit::Packs)), None);
+    }
+
+    #[test]
+    fn test_deserialize_plain_number() {
+        assert_eq!(serde_json::from_str::<Amount>("3").unwrap(), Amount::pieces(3.0));
+        let json = serde_json::to_string(&Amount::new(0.25, Unit::Litre)).unwrap();
+        assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), Amount::new(0.25, Unit::Litre));
+    }
+}
@@ -0,0 +1,194 @@
+//! Imports OpenFoodFacts data dumps into the `catalog` table of the
+//! [`LocalCatalog`](super::provider::LocalCatalog), so barcode lookups work
+//! offline.
+//!
+//! Both the JSONL dump (one product document per line) and the CSV export
+//! (tab-separated) are read line by line, so the dumps don't have to fit into
+//! memory. Only the fields which the app requests from the API are stored.
+
+use super::openfoodsfacts::field_names;
+use crate::{barcode_scanner::Barcode, db::DB, error::Result};
+use leptos::logging;
+use serde_json::{Map, Value};
+use std::{collections::HashSet, io::BufRead};
+
+/// The number of products inserted per transaction.
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Jsonl,
+    /// The tab-separated CSV export, e.g. `en.openfoodfacts.org.products.csv`.
+    Csv,
+}
+
+impl Format {
+    /// Guesses the format from the file name. Compressed dumps have to be
+    /// decompressed first, e.g. with `zcat`.
+    pub fn from_path(path: &str) -> Format {
+        if path.ends_with(".csv") || path.ends_with(".tsv") {
+            Format::Csv
+        } else {
+            Format::Jsonl
+        }
+    }
+}
+
+impl TryFrom<&str> for Format {
+    type Error = String;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "jsonl" => Ok(Format::Jsonl),
+            "csv" | "tsv" => Ok(Format::Csv),
+            f => Err(format!("unknown format: {f:?}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Only products sold in one of these countries are imported, e.g.
+    /// "en:germany". Empty means all products.
+    pub countries: Vec<String>,
+}
+
+impl ImportOptions {
+    /// Accepts "germany" as well as "en:germany".
+    pub fn add_country(&mut self, country: &str) {
+        let country = country.trim().to_lowercase();
+        let country = if country.contains(':') { country } else { format!("en:{country}") };
+        self.countries.push(country);
+    }
+
+    fn accepts(&self, product: &Map<String, Value>) -> bool {
+        if self.countries.is_empty() {
+            return true;
+        }
+        let Some(Value::Array(tags)) = product.get("countries_tags") else { return false };
+        tags.iter().filter_map(Value::as_str).any(|t| self.countries.iter().any(|c| c == t))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    pub imported: usize,
+    /// Not sold in the selected countries.
+    pub filtered: usize,
+    /// Unparsable lines and products without a valid EAN, UPC or GTIN-14 code.
+    pub invalid: usize,
+}
+
+/// Stored as [`Barcode::gtin`], so UPC codes in the dump match scanned EAN-13 codes.
+fn parse_barcode(code: &Value) -> Option<Barcode> {
+    match code {
+        Value::String(code) => Barcode::from_digits(code.trim()).ok(),
+        code => Barcode::from_digits(&code.as_u64()?.to_string()).ok(),
+    }
+}
+
+/// Maps the columns of the CSV export to the fields of the API.
+fn csv_product(header: &[&str], line: &str) -> Map<String, Value> {
+    let mut product = Map::new();
+    let mut nutriments = Map::new();
+    for (&column, value) in header.iter().zip(line.split('\t')) {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let column = match column {
+            "allergens" => "allergens_tags",
+            "image_small_url" => "image_thumb_url",
+            column => column,
+        };
+        if column.ends_with("_100g") {
+            if let Ok(n) = value.parse::<f64>() {
+                nutriments.insert(column.to_string(), n.into());
+            }
+        } else if column.ends_with("_tags") {
+            let tags = value.split(',').map(|t| Value::from(t.trim())).collect();
+            product.insert(column.to_string(), Value::Array(tags));
+        } else if column == "nova_group" {
+            if let Ok(n) = value.parse::<u8>() {
+                product.insert(column.to_string(), n.into());
+            }
+        } else {
+            product.insert(column.to_string(), value.into());
+        }
+    }
+    if !nutriments.is_empty() {
+        product.insert("nutriments".to_string(), Value::Object(nutriments));
+    }
+    product
+}
+
+async fn insert_batch(batch: &mut Vec<(Barcode, String)>, db: &DB) -> Result<()> {
+    let mut transaction = db.begin_transaction().await?;
+    for (barcode, product) in batch.drain(..) {
+        sqlx::query!(
+            "INSERT OR REPLACE INTO catalog(barcode, product) VALUES ( ?, ? )",
+            barcode,
+            product
+        )
+        .execute(transaction.as_mut())
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Imports the products in `reader` into the catalog. Existing entries are
+/// replaced.
+pub async fn import(
+    reader: impl BufRead,
+    format: Format,
+    options: &ImportOptions,
+    db: &DB,
+) -> Result<ImportStats> {
+    let fields = field_names().into_iter().collect::<HashSet<_>>();
+    let mut lines = reader.lines();
+    let header = match format {
+        Format::Jsonl => None,
+        Format::Csv => Some(lines.next().transpose()?.unwrap_or_default()),
+    };
+    let header = header.as_ref().map(|h| h.trim_end().split('\t').collect::<Vec<_>>());
+
+    let mut stats = ImportStats::default();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let product = match &header {
+            None => match serde_json::from_str::<Value>(&line) {
+                Ok(Value::Object(product)) => product,
+                _ => {
+                    stats.invalid += 1;
+                    continue;
+                },
+            },
+            Some(header) => csv_product(header, &line),
+        };
+        if !options.accepts(&product) {
+            stats.filtered += 1;
+            continue;
+        }
+        let Some(barcode) = product.get("code").and_then(parse_barcode) else {
+            stats.invalid += 1;
+            continue;
+        };
+
+        let product: Map<_, _> = product.into_iter().filter(|(k, _)| fields.contains(k)).collect();
+        batch.push((barcode, Value::Object(product).to_string()));
+        if batch.len() >= BATCH_SIZE {
+            insert_batch(&mut batch, db).await?;
+        }
+        stats.imported += 1;
+        if stats.imported % (100 * BATCH_SIZE) == 0 {
+            logging::log!("imported {} products", stats.imported);
+        }
+    }
+    insert_batch(&mut batch, db).await?;
+    Ok(stats)
+}
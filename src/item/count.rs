@@ -1,22 +1,46 @@
+use super::amount::{Amount, Unit};
 use leptos::*;
 
 #[component]
 pub fn ItemCount<S>(amount: S) -> impl IntoView
-where S: SignalUpdate<Value = u64> + SignalGet<Value = u64> + Copy + 'static {
-    let inc = move |_| amount.update(|x| *x = x.saturating_add(1));
-    let dec = move |_| amount.update(|x| *x = x.saturating_sub(1));
+where S: SignalUpdate<Value = Amount> + SignalGet<Value = Amount> + Copy + 'static {
+    let inc = move |_| amount.update(|x| *x = x.step_up());
+    let dec = move |_| amount.update(|x| *x = x.step_down());
+    let set_unit = move |ev| {
+        if let Ok(unit) = Unit::try_from(event_target_value(&ev).as_str()) {
+            amount.update(|x| x.unit = unit)
+        }
+    };
     view! {
         <div class="item-count">
             // <button class="cursor-pointer" on:click=inc>"+"</button>
             <input
                 type="number"
                 min="0"
-                value=move || amount.get()
-                prop:value=move || amount.get()
-                on:change=move |ev| amount.update(
-                    |c| *c = event_target_value(&ev).parse().unwrap_or(*c)
-                )
+                step=move || amount.get().unit.step()
+                value=move || amount.get().value()
+                prop:value=move || amount.get().value()
+                on:change=move |ev| amount.update(|x| {
+                    if let Ok(value) = event_target_value(&ev).replace(',', ".").parse() {
+                        *x = x.with_value(value)
+                    }
+                })
             />
+            <select class="unit cursor-pointer" title="Unit" on:change=set_unit>
+                {
+                    Unit::ALL
+                        .into_iter()
+                        .map(|unit| view! {
+                            <option
+                                value=unit.as_str()
+                                selected=move || amount.get().unit == unit
+                            >
+                                { unit.as_str() }
+                            </option>
+                        })
+                        .collect_view()
+                }
+            </select>
             <button class="cursor-pointer" on:click=inc>"+"</button>
             <button class="cursor-pointer" on:click=dec>"-"</button>
         </div>
@@ -24,15 +48,18 @@ where S: SignalUpdate<Value = u64> + SignalGet<Value = u64> + Copy + 'static {
 }
 
 #[component]
-pub fn ItemCountDisabled(amount: u64) -> impl IntoView {
+pub fn ItemCountDisabled(amount: Amount) -> impl IntoView {
     view! {
         <div class="item-count">
             // <button disabled>"+"</button>
             <input
                 type="number"
-                value=amount
+                value=amount.value()
                 disabled
             />
+            <select class="unit" disabled>
+                <option>{ amount.unit.as_str() }</option>
+            </select>
             <button disabled>"+"</button>
             <button disabled>"-"</button>
         </div>
@@ -1,23 +1,84 @@
 use super::{
+    amount::{Amount, Unit},
     server_functions::insert_from_client,
     variant_data::{NewVariant, Variant, VariantImpl},
 };
-use crate::{barcode_scanner::Barcode, error::Result};
+use crate::{
+    barcode_scanner::Barcode,
+    error::{Error, Result},
+    language::Language,
+};
 #[cfg(feature = "ssr")]
 use crate::{db::DBType, db::DB};
+use chrono::NaiveDate;
 use leptos::{create_server_action, logging, ServerFnErrorErr};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use sqlx::SqliteConnection;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ItemImpl<ID> {
     pub id: ID,
-    pub amount: u64,
+    pub amount: Amount,
     pub completed: bool,
+    #[serde(default)]
+    pub priority: bool,
+    #[serde(default)]
+    pub needed_by: Option<NaiveDate>,
     pub variants: Vec<VariantImpl<ID>>,
 }
 
+impl<ID> ItemImpl<ID> {
+    /// The name of the first variant.
+    pub fn name(&self) -> &str {
+        self.variants.first().map(|v| v.name.as_str()).unwrap_or_default()
+    }
+
+    pub fn urgency(&self, today: NaiveDate) -> Urgency {
+        Urgency::of(self.priority, self.needed_by, self.completed, today)
+    }
+}
+
+/// Ordered from least to most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+    Normal,
+    Urgent,
+    Overdue,
+}
+
+impl Urgency {
+    /// Items which are needed within this many days are [`Urgency::Urgent`].
+    pub const URGENT_DAYS: i64 = 2;
+
+    pub fn of(
+        priority: bool,
+        needed_by: Option<NaiveDate>,
+        completed: bool,
+        today: NaiveDate,
+    ) -> Urgency {
+        match needed_by {
+            _ if completed => Urgency::Normal,
+            Some(date) if date < today => Urgency::Overdue,
+            Some(date) if (date - today).num_days() <= Self::URGENT_DAYS => Urgency::Urgent,
+            _ if priority => Urgency::Urgent,
+            _ => Urgency::Normal,
+        }
+    }
+}
+
 pub type Item = ItemImpl<i64>;
 
+impl Item {
+    pub fn without_id(self) -> NewItem {
+        NewItem {
+            id: (),
+            variants: self.variants.into_iter().map(Variant::without_id).collect(),
+            ..self
+        }
+    }
+}
+
 #[cfg(feature = "ssr")]
 impl Item {
     pub async fn select_by_id(id: i64, db: &DB) -> Result<Self> {
@@ -30,7 +91,13 @@ impl Item {
 
     pub async fn select_all(db: &DB) -> Result<Vec<Self>> {
         let mut conn = db.connection().await?;
-        let rows = sqlx::query_as!(ItemRow, "SELECT * FROM item").fetch_all(conn.as_mut()).await?;
+        let rows = sqlx::query_as!(
+            ItemRow,
+            r#"SELECT id, amount, unit, completed, priority, needed_by as "needed_by: NaiveDate"
+            FROM item"#
+        )
+        .fetch_all(conn.as_mut())
+        .await?;
         let mut items = Vec::with_capacity(rows.len());
         for row in rows {
             items.push(row.fetch_variants(conn.as_mut()).await?)
@@ -60,7 +127,14 @@ pub type NewItem = ItemImpl<()>;
 
 impl Default for NewItem {
     fn default() -> Self {
-        Self { id: (), amount: 1, completed: false, variants: vec![] }
+        Self {
+            id: (),
+            amount: Amount::default(),
+            completed: false,
+            priority: false,
+            needed_by: None,
+            variants: vec![],
+        }
     }
 }
 
@@ -69,34 +143,90 @@ impl NewItem {
         Self { variants: vec![NewVariant::default()], ..Self::default() }
     }
 
-    pub async fn from_barcode(barcode: Barcode) -> Result<Self> {
-        Ok(Self { variants: vec![NewVariant::from_barcode(barcode).await?], ..Self::default() })
+    pub async fn from_barcode(barcode: Barcode, lang: Language) -> Result<Self> {
+        let variant = NewVariant::from_barcode(barcode, lang).await?;
+        Ok(Self { variants: vec![variant], ..Self::default() })
     }
 
     #[cfg(feature = "ssr")]
     pub async fn insert(self, db: &DB) -> Result<Item> {
-        logging::log!("insert item: {:?}", self);
         let mut tx = db.begin_transaction().await?;
+        let item = self.insert_with(tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(item)
+    }
 
-        let amount = self.amount as i64;
+    #[cfg(feature = "ssr")]
+    async fn insert_with(self, conn: &mut SqliteConnection) -> Result<Item> {
+        logging::log!("insert item: {:?}", self);
+        let amount = self.amount.value();
+        let unit = self.amount.unit.as_str();
         let id = sqlx::query!(
-            "INSERT INTO item(amount, completed) VALUES ( ?, ? )",
+            r#"INSERT INTO item(amount, unit, completed, priority, needed_by)
+            VALUES ( ?, ?, ?, ?, ? )"#,
             amount,
-            self.completed
+            unit,
+            self.completed,
+            self.priority,
+            self.needed_by
         )
-        .execute(tx.as_mut())
+        .execute(&mut *conn)
         .await?
         .last_insert_rowid();
 
         let mut variants = Vec::with_capacity(self.variants.len());
         for v in self.variants {
-            variants.push(v.insert(id, tx.as_mut()).await?);
+            variants.push(v.insert(id, &mut *conn).await?);
         }
 
-        tx.commit().await?;
         Ok(Item { id, variants, ..self })
     }
 
+    /// Like [`NewItem::insert`], but if an item with a matching variant is
+    /// already on the list, the amount is added to that item instead. Amounts
+    /// are converted between compatible units (g and kg, ml and l). If the
+    /// units aren't compatible, a new item is inserted.
+    #[cfg(feature = "ssr")]
+    pub async fn insert_or_merge(self, db: &DB) -> Result<Item> {
+        let mut tx = db.begin_transaction().await?;
+        let id = self.merge_with(tx.as_mut()).await?;
+        tx.commit().await?;
+        Item::select_by_id(id, db).await
+    }
+
+    /// Calls [`NewItem::insert_or_merge`] for all `items` in a single
+    /// transaction. Returns the ids of the inserted or updated items.
+    #[cfg(feature = "ssr")]
+    pub async fn insert_or_merge_all(items: Vec<Self>, db: &DB) -> Result<Vec<i64>> {
+        let mut tx = db.begin_transaction().await?;
+        let mut ids = Vec::with_capacity(items.len());
+        for item in items {
+            ids.push(item.merge_with(tx.as_mut()).await?);
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    /// Like [`NewItem::insert_or_merge`], but on an open transaction. Returns
+    /// the id of the inserted or updated item.
+    #[cfg(feature = "ssr")]
+    pub(crate) async fn merge_with(self, conn: &mut SqliteConnection) -> Result<i64> {
+        let Some(id) = ItemRow::find_matching(&self.variants, &mut *conn).await? else {
+            return Ok(self.insert_with(conn).await?.id);
+        };
+        let existing = ItemRow::select_by_id(id, &mut *conn).await?.amount()?;
+        let Some(sum) = existing.checked_add(self.amount) else {
+            logging::log!("can't merge {} into {existing} of {id}", self.amount);
+            return Ok(self.insert_with(conn).await?.id);
+        };
+        logging::log!("merge item into {id}: {:?}", self);
+        let amount = sum.value();
+        sqlx::query!("UPDATE item SET amount = ?, completed = FALSE WHERE id = ?", amount, id)
+            .execute(&mut *conn)
+            .await?;
+        Ok(id)
+    }
+
     #[cfg(not(feature = "ssr"))]
     pub async fn insert_from_client(self) -> Result<Item> {
         let ids = insert_from_client(self.clone()).await.map_err(ServerFnErrorErr::from)?;
@@ -124,8 +254,11 @@ impl From<NewItem> for PendingItem {
 #[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct ItemRow {
     pub id: i64,
-    pub amount: i64,
+    pub amount: f64,
+    pub unit: String,
     pub completed: bool,
+    pub priority: bool,
+    pub needed_by: Option<NaiveDate>,
 }
 
 #[cfg(feature = "ssr")]
@@ -134,21 +267,65 @@ impl ItemRow {
         id: i64,
         conn: impl sqlx::Executor<'_, Database = DBType>,
     ) -> Result<Self> {
-        Ok(sqlx::query_as!(ItemRow, "SELECT * FROM item WHERE id = ?", id)
-            .fetch_one(conn)
-            .await?)
+        Ok(sqlx::query_as!(
+            ItemRow,
+            r#"SELECT id, amount, unit, completed, priority, needed_by as "needed_by: NaiveDate"
+            FROM item WHERE id = ?"#,
+            id
+        )
+        .fetch_one(conn)
+        .await?)
     }
 
     pub async fn fetch_variants(
         self,
         conn: impl sqlx::Executor<'_, Database = DBType>,
     ) -> Result<Item> {
-        let Self { id, amount, completed } = self;
+        let amount = self.amount()?;
+        let Self { id, completed, priority, needed_by, .. } = self;
         let variants = Variant::for_item(id, conn).await?;
-        Ok(Item { id, amount: saturating_as(amount), completed, variants })
+        Ok(Item { id, amount, completed, priority, needed_by, variants })
     }
-}
 
-fn saturating_as(int: i64) -> u64 {
-    int.max(0) as u64
+    pub fn amount(&self) -> Result<Amount> {
+        let unit = Unit::try_from(self.unit.as_str())
+            .map_err(|unit| Error::InvalidDBValue(format!("unknown unit: {unit}")))?;
+        Ok(Amount::new(self.amount, unit))
+    }
+
+    /// Returns the id of an item which has a variant with the same barcode or
+    /// (case-insensitive) name as one of `variants`.
+    pub async fn find_matching(
+        variants: &[NewVariant],
+        conn: &mut SqliteConnection,
+    ) -> Result<Option<i64>> {
+        for v in variants {
+            if let Some(barcode) = v.barcode.0 {
+                let id = sqlx::query_scalar!(
+                    "SELECT variant_of FROM item_variant WHERE barcode = ? LIMIT 1",
+                    barcode
+                )
+                .fetch_optional(&mut *conn)
+                .await?;
+                if id.is_some() {
+                    return Ok(id);
+                }
+            }
+
+            let name = v.name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let id = sqlx::query_scalar!(
+                "SELECT variant_of FROM item_variant WHERE name = ? COLLATE NOCASE LIMIT 1",
+                name
+            )
+            .fetch_optional(&mut *conn)
+            .await?;
+            if id.is_some() {
+                return Ok(id);
+            }
+        }
+        Ok(None)
+    }
 }
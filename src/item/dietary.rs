@@ -0,0 +1,48 @@
+use super::openfoodsfacts::OpenFoodFactsProduct;
+use crate::{
+    profile::{ActiveProfiles, Severity},
+    util::json_column,
+};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// The OpenFoodFacts tags of a product which are checked against the
+/// [`crate::profile::Profile`]s, e.g. "en:nuts" or "en:non-vegan".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dietary {
+    pub allergens: Vec<String>,
+    pub traces: Vec<String>,
+    /// `ingredients_analysis_tags`
+    pub analysis: Vec<String>,
+}
+
+json_column!(Dietary);
+
+impl From<&OpenFoodFactsProduct> for Dietary {
+    fn from(product: &OpenFoodFactsProduct) -> Self {
+        Dietary {
+            allergens: product.allergens_tags.clone(),
+            traces: product.traces_tags.clone(),
+            analysis: product.ingredients_analysis_tags.clone(),
+        }
+    }
+}
+
+/// Warns if the product conflicts with an active profile. Renders nothing
+/// without an [`ActiveProfiles`] context.
+#[component]
+pub fn DietaryWarning(#[prop(into)] dietary: MaybeSignal<Dietary>) -> impl IntoView {
+    let profiles = use_context::<ActiveProfiles>();
+    let conflicts =
+        move || profiles.map(|p| dietary.with(|d| p.conflicts(d))).unwrap_or_default();
+
+    move || {
+        let conflicts = conflicts();
+        (!conflicts.is_empty()).then(|| {
+            let certain = conflicts.iter().any(|c| c.severity == Severity::Certain);
+            let title = conflicts.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+            view! { <span class="dietary-warning" certain=certain title=title>"⚠"</span> }
+        })
+    }
+}
@@ -1,26 +1,47 @@
+pub mod amount;
+#[cfg(feature = "ssr")]
+pub mod catalog;
 mod count;
 pub mod data;
+pub mod dietary;
+pub mod nutrition;
 pub mod openfoodsfacts;
+pub mod package;
+#[cfg(feature = "ssr")]
+pub mod provider;
+pub mod scores;
 pub mod server_functions;
+pub mod urgency;
 pub mod variant_data;
 
 use self::{
     count::{ItemCount, ItemCountDisabled},
-    data::{Item, PendingItem},
+    data::{Item, PendingItem, Urgency},
+    dietary::DietaryWarning,
+    nutrition::NutritionView,
+    openfoodsfacts::{
+        contribute::{contribute_product, contributions_enabled, Contribution},
+        LookupError,
+    },
+    package::{Package, PackageView},
+    scores::ScoreBadges,
+    urgency::{today, ItemUrgency},
     variant_data::{PendingVariant, VariantImpl},
 };
 use crate::{
-    barcode_scanner::{Barcode, BarcodeScanner},
+    barcode_scanner::{Barcode, BarcodeScanner, Gs1Data},
+    camera::photo_data_url,
     default_resource::DefaultResource,
     image::Image,
     item::{
         data::NewItem,
         server_functions::{
-            set_amount, set_completed, InsertFromClient, InsertFromClientAction,
-            InsertVariantFromClient, RemoveItem,
+            set_amount, set_completed, set_needed_by, set_priority, InsertFromClient,
+            InsertFromClientAction, InsertVariantFromClient, RemoveItem,
         },
         variant_data::{NewVariant, Variant},
     },
+    language::Language,
     list::ListResource,
     option_signal::OptionSignal,
     popup::{Popup, PopupSignal},
@@ -29,7 +50,10 @@ use crate::{
     util::{force_use_context, on_render, on_render_elem, SignalUpdateSome, SignalWithMap},
 };
 use leptos::{html::Div, *};
-use web_sys::{Event, HtmlElement, MouseEvent};
+use web_sys::{Event, HtmlElement, HtmlInputElement, MouseEvent};
+
+/// The maximum width and height of the photos for contributions.
+const PHOTO_SIZE: u32 = 1200;
 
 fn stop_prop(f: impl Fn()) -> impl Fn(MouseEvent) {
     return move |ev| {
@@ -43,10 +67,14 @@ pub struct VariantsContainer(NodeRef<Div>);
 
 #[component]
 pub fn ItemView(item: Item) -> impl IntoView {
-    let Item { id, amount, completed, variants } = item;
+    let Item { id, amount, completed, priority, needed_by, variants } = item;
 
     let completed = ServerSyncSignal::new(completed, move |next| set_completed(id, next));
     let amount = ServerSyncSignal::new(amount, move |next| set_amount(id, next));
+    let priority = ServerSyncSignal::new(priority, move |next| set_priority(id, next));
+    let needed_by = ServerSyncSignal::new(needed_by, move |next| set_needed_by(id, next));
+    let urgency =
+        Signal::derive(move || Urgency::of(priority(), needed_by(), completed(), today()));
 
     let variants = create_rw_signal(variants);
     let new_variants = NewVariantsSignal::new();
@@ -75,7 +103,9 @@ pub fn ItemView(item: Item) -> impl IntoView {
             class="item"
             expanded=is_expanded
             checked=completed
-            archived=move || amount() == 0
+            archived=move || amount().is_zero()
+            urgent=move || urgency() == Urgency::Urgent
+            overdue=move || urgency() == Urgency::Overdue
         >
             <input
                 type="checkbox"
@@ -105,6 +135,7 @@ pub fn ItemView(item: Item) -> impl IntoView {
             </div>
             <div class="rhs">
                 <ItemCount amount />
+                <ItemUrgency priority needed_by urgency />
                 <img
                     src="img/trash-alt-svgrepo-com.svg"
                     alt="Remove Item"
@@ -125,27 +156,66 @@ impl IntoView for Item {
 
 #[component]
 pub fn VariantView(variant: Variant) -> impl IntoView {
-    let Variant { id, name, shop, barcode, brands, img_url, thumb_url, packaging, quantity } =
-        variant;
+    let Variant {
+        id,
+        name,
+        name_lang,
+        shop,
+        barcode,
+        brands,
+        img_url,
+        thumb_url,
+        packaging,
+        quantity,
+        nutrition,
+        scores,
+        dietary,
+        package,
+    } = variant;
 
     let edit_variant = || window().alert_with_message("TODO: Edit Variant").unwrap();
     let delete_variant = || window().alert_with_message("TODO: Delete Variant").unwrap();
 
+    let barcode_popup = PopupSignal::new();
+    let barcode_svg = barcode.0.and_then(|b| b.to_svg());
+    let show_barcode = barcode_svg.map(|svg| {
+        view! {
+            <img
+                src="img/barcode-svgrepo-com.svg"
+                alt="Show Barcode"
+                title="Show Barcode"
+                class="show-barcode-button cursor-pointer"
+                on:click=stop_prop(move || barcode_popup.open())
+            />
+            <Popup popup=barcode_popup>
+                <div class="barcode-image">
+                    <div inner_html=svg.clone() />
+                    <p>"Turn up the screen brightness if the scanner can't read the barcode."</p>
+                </div>
+            </Popup>
+        }
+    });
+
     view! {
         <div class="variant">
             <div class="image">
                 <Image thumb_url full_url=img_url/>
             </div>
             <div class="infos">
-                <span class="name">{ name }</span>
-                <span class="quantity sub-info">{ quantity }</span>
+                <span class="name" lang=name_lang>{ name }</span>
+                <span class="quantity sub-info">{ quantity.clone() }</span>
                 <span class="brands sub-info">{ brands }</span>
+                <PackageView package />
+                <DietaryWarning dietary />
+                <ScoreBadges scores />
+                <NutritionView nutrition quantity />
                 // <span class="sub-info">{ format!("{}{}{}",
                 //     quantity,
                 //     if quantity.len() > 0 { ", " } else { "" },
                 //     brands)
                 // }</span>
                 <div class="buttons">
+                    {show_barcode}
                     <img
                         src="img/pen-square-svgrepo-com.svg"
                         //src="img/pen-svgrepo-com.svg"
@@ -177,16 +247,24 @@ impl IntoView for Variant {
 pub struct VariantSignal {
     barcode: OptionSignal<RwSignal<Option<Barcode>>>,
     variant: DefaultResource<Option<Barcode>, NewVariant>,
+    /// Why the last lookup failed. The user can contribute unknown products.
+    lookup_error: RwSignal<Option<LookupError>>,
+    contribute: RwSignal<bool>,
+    /// A `data:` URL of the photo for the contribution.
+    photo: RwSignal<Option<String>>,
+    /// From the GS1 code, if the product was scanned from one.
+    package: RwSignal<Package>,
 }
 
 impl VariantSignal {
     pub fn new() -> Self {
         let barcode = OptionSignal::new();
+        let lookup_error = create_rw_signal(None);
         let variant = DefaultResource::new_local(
             barcode,
             move |barcode| async move {
                 match barcode {
-                    Some(barcode) => NewVariant::from_barcode(barcode)
+                    Some(barcode) => NewVariant::from_barcode(barcode, Language::from_navigator())
                         .await
                         /*
                         .inspect(|a| window().alert_with_message(&format!("{:?}", a)).unwrap())
@@ -197,6 +275,8 @@ impl VariantSignal {
                         })
                         */
                         .inspect_err(|e| logging::error!("ItemData::from_barcode error: {}", e))
+                        .inspect(|_| lookup_error.set(None))
+                        .inspect_err(|e| lookup_error.set(Some(LookupError::from(e))))
                         .ok(),
                     None => None,
                 }
@@ -204,7 +284,53 @@ impl VariantSignal {
             },
             NewVariant::default,
         );
-        VariantSignal { barcode, variant }
+        let contribute = create_rw_signal(false);
+        let photo = create_rw_signal(None);
+        let package = create_rw_signal(Package::default());
+        VariantSignal { barcode, variant, lookup_error, contribute, photo, package }
+    }
+
+    pub fn reset(&self) {
+        self.barcode.reset();
+        self.variant.reset();
+        self.lookup_error.set(None);
+        self.contribute.set(false);
+        self.photo.set(None);
+        self.package.set(Package::default());
+    }
+
+    /// The looked up and edited variant with the [`Package`] of the scanned
+    /// code.
+    pub fn new_variant(&self) -> NewVariant {
+        NewVariant { package: self.package.get(), ..self.variant.get() }
+    }
+
+    /// Looks up the barcode again, e.g. after a timeout.
+    pub fn retry(&self) {
+        if let Some(barcode) = self.barcode.get_untracked() {
+            self.barcode.set(barcode);
+        }
+    }
+
+    /// The product data for OpenFoodFacts if the user chose to share it.
+    pub fn contribution(&self) -> Option<Contribution> {
+        if !self.contribute.get_untracked() {
+            return None;
+        }
+        let barcode = self.barcode.get_untracked()?;
+        let NewVariant { name, brands, quantity, .. } = self.variant.get();
+        let (lang, photo) = (Language::from_navigator(), self.photo.get_untracked());
+        Some(Contribution { barcode, name, lang, brands, quantity, photo })
+    }
+
+    /// Queues the [`VariantSignal::contribution`] on the server.
+    pub fn submit_contribution(&self) {
+        let Some(contribution) = self.contribution() else { return };
+        spawn_local(async move {
+            if let Err(err) = contribute_product(contribution).await {
+                logging::error!("ERROR while contributing the product: {}", err);
+            }
+        });
     }
 }
 
@@ -227,21 +353,22 @@ impl NewVariantsSignal {
     pub fn reset_to_one_variant(&self) {
         self.0.update(|v| match v.len() {
             0 => v.push(VariantSignal::new()),
-            1 => {
-                v[0].barcode.reset();
-                v[0].variant.reset();
-            },
+            1 => v[0].reset(),
             _ => drop(v.drain(1..)),
         })
     }
 
     pub fn to_variants_vec(&self) -> Vec<NewVariant> {
-        self.0.with(|v| v.iter().map(|t| t.variant.get()).collect())
+        self.0.with(|v| v.iter().map(VariantSignal::new_variant).collect())
     }
 
     pub fn is_empty(&self) -> bool {
         self.0.with(Vec::is_empty)
     }
+
+    pub fn submit_contributions(&self) {
+        self.0.with_untracked(|v| v.iter().for_each(VariantSignal::submit_contribution))
+    }
 }
 
 #[component]
@@ -249,6 +376,10 @@ pub fn NewItemView(show: RwSignal<bool>) -> impl IntoView {
     let default_item = NewItem::default();
     let amount = create_rw_signal(default_item.amount);
     let completed = create_rw_signal(default_item.completed);
+    let priority = create_rw_signal(default_item.priority);
+    let needed_by = create_rw_signal(default_item.needed_by);
+    let urgency =
+        Signal::derive(move || Urgency::of(priority(), needed_by(), completed(), today()));
     let new_variants = NewVariantsSignal::new();
     new_variants.add_empty_variant();
 
@@ -256,6 +387,8 @@ pub fn NewItemView(show: RwSignal<bool>) -> impl IntoView {
         let default_item = NewItem::default();
         amount.set(default_item.amount);
         completed.set(default_item.completed);
+        priority.set(default_item.priority);
+        needed_by.set(default_item.needed_by);
         new_variants.reset_to_one_variant();
     };
 
@@ -266,6 +399,8 @@ pub fn NewItemView(show: RwSignal<bool>) -> impl IntoView {
         id: (),
         amount: amount(),
         completed: completed(),
+        priority: priority(),
+        needed_by: needed_by(),
         variants: new_variants.to_variants_vec(),
     };
 
@@ -282,6 +417,7 @@ pub fn NewItemView(show: RwSignal<bool>) -> impl IntoView {
             window().alert_with_message("empty Name not allowed").unwrap();
         } else {
             insert_from_client.0.dispatch(InsertFromClient { new_item: item() });
+            new_variants.submit_contributions();
         };
         reset();
         show.set(false);
@@ -313,6 +449,7 @@ pub fn NewItemView(show: RwSignal<bool>) -> impl IntoView {
             </div>
             <div class="rhs">
                 <ItemCount amount />
+                <ItemUrgency priority needed_by urgency />
                 <img
                     src="img/check-svgrepo-com.svg"
                     alt="Save Item"
@@ -345,7 +482,8 @@ pub fn NewVariantView(
 
     let barcode_popup = PopupSignal::new();
 
-    let VariantSignal { barcode, variant } = variant;
+    let variant_signal = variant;
+    let VariantSignal { barcode, variant, lookup_error, contribute, photo, package } = variant;
     let name = subsignal!(variant => name);
     let shop = subsignal!(variant => shop);
     let brands = subsignal!(variant => brands);
@@ -358,16 +496,39 @@ pub fn NewVariantView(
     };
     let packaging = subsignal!(variant => packaging);
     let quantity = subsignal!(variant => quantity);
+    let dietary = Signal::derive(move || variant.with(|v| v.dietary.clone()));
 
     let add_variant = move |_| match item_id {
         Some(_) if name.with(|s| s.trim().is_empty()) => {
             window().alert_with_message("empty Name not allowed").unwrap()
         },
-        Some(item_id) => create_server_action()
-            .dispatch(InsertVariantFromClient { item_id, new_variant: variant() }),
+        Some(item_id) => {
+            let new_variant = variant_signal.new_variant();
+            create_server_action().dispatch(InsertVariantFromClient { item_id, new_variant });
+            variant_signal.submit_contribution();
+        },
         None => (),
     };
+
+    let not_found = move || lookup_error.with(|e| e == &Some(LookupError::NotFound));
+    let can_contribute = create_local_resource(not_found, |not_found| async move {
+        not_found && contributions_enabled().await.unwrap_or_default()
+    });
+    let set_photo = move |ev: Event| {
+        let Some(file) = event_target::<HtmlInputElement>(&ev).files().and_then(|f| f.get(0))
+        else {
+            return photo.set(None);
+        };
+        spawn_local(async move {
+            match photo_data_url(file, PHOTO_SIZE).await {
+                Ok(url) => photo.set(Some(url)),
+                Err(err) => logging::error!("ERROR while loading the photo: {:?}", err),
+            }
+        })
+    };
     let discard_variant = |_| window().alert_with_message("TODO: Discard Variant").unwrap();
+    // Reported by the scanner right before the barcode of a GS1 code.
+    let gs1_data = create_rw_signal(None::<Gs1Data>);
 
     view! {
         <div
@@ -385,10 +546,16 @@ pub fn NewVariantView(
                     title="Scan Barcode"
                 />
                 <Popup popup=barcode_popup>
-                    <BarcodeScanner set_barcode=move |b| {
-                        barcode.set(b);
-                        barcode_popup.close();
-                    } />
+                    <BarcodeScanner
+                        set_gs1_data=move |data| gs1_data.set(Some(data))
+                        set_barcode=move |b| {
+                            let data = gs1_data.get_untracked().filter(|d| d.gtin == Some(b));
+                            package.set(data.as_ref().map(Package::from).unwrap_or_default());
+                            gs1_data.set(None);
+                            barcode.set(b);
+                            barcode_popup.close();
+                        }
+                    />
                 </Popup>
             </div>
             //<input type="file" accept="image/*" class="image-input" />
@@ -414,6 +581,40 @@ pub fn NewVariantView(
                     prop:value=move || quantity()
                     on:change=move |ev| quantity.set(event_target_value(&ev))
                 />
+                <DietaryWarning dietary />
+                {move || {
+                    lookup_error()
+                        .map(|err| {
+                            let retry = err.is_retryable().then(|| view! {
+                                <button on:click=move |_| variant_signal.retry()>"Retry"</button>
+                            });
+                            view! {
+                                <span class="lookup-error sub-info">
+                                    {err.message().to_string()}
+                                    {retry}
+                                </span>
+                            }
+                        })
+                }}
+                <Show when=move || can_contribute().unwrap_or_default()>
+                    <label class="contribute sub-info" title="Unknown product">
+                        <input
+                            type="checkbox"
+                            prop:checked=contribute
+                            on:change=move |ev| contribute.set(event_target_checked(&ev))
+                        />
+                        "Share with OpenFoodFacts"
+                    </label>
+                    <input
+                        type="file"
+                        accept="image/*"
+                        capture="environment"
+                        class="photo-input sub-info"
+                        title="Photo of the front"
+                        hidden=move || !contribute()
+                        on:change=set_photo
+                    />
+                </Show>
                 <div class="buttons">
                     <Show when=move || item_id.is_some()>
                         <img
@@ -457,7 +658,7 @@ pub fn AddVariantButtonView(new_variants: NewVariantsSignal) -> impl IntoView {
 
 #[component]
 pub fn PendingItemView(item: PendingItem) -> impl IntoView {
-    let NewItem { id, amount, completed, variants } = item.0;
+    let NewItem { id, amount, completed, variants, .. } = item.0;
 
     let variants = create_rw_signal(variants);
 
@@ -0,0 +1,114 @@
+use super::{
+    amount::{Amount, Unit},
+    openfoodsfacts::Nutriments,
+};
+use crate::util::json_column;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Nutrition facts per 100 g or 100 ml. Missing values are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Nutrition {
+    pub energy_kcal: Option<f32>,
+    pub fat: Option<f32>,
+    pub saturated_fat: Option<f32>,
+    pub carbohydrates: Option<f32>,
+    pub sugars: Option<f32>,
+    pub fiber: Option<f32>,
+    pub proteins: Option<f32>,
+    pub salt: Option<f32>,
+}
+
+/// The values come from JSON, which can't contain NaN.
+impl Eq for Nutrition {}
+
+impl From<&Nutriments> for Nutrition {
+    fn from(n: &Nutriments) -> Self {
+        Nutrition {
+            energy_kcal: n.energy_kcal_100g,
+            fat: n.fat_100g,
+            saturated_fat: n.saturated_fat_100g,
+            carbohydrates: n.carbohydrates_100g,
+            sugars: n.sugars_100g,
+            fiber: n.fiber_100g,
+            proteins: n.proteins_100g,
+            salt: n.salt_100g,
+        }
+    }
+}
+
+impl Nutrition {
+    pub fn is_empty(&self) -> bool {
+        *self == Nutrition::default()
+    }
+
+    /// `(short label, label, value)` for every nutrient.
+    fn columns(&self) -> [(&'static str, &'static str, Option<f32>); 8] {
+        [
+            ("kcal", "Energy (kcal)", self.energy_kcal),
+            ("Fat", "Fat (g)", self.fat),
+            ("Sat.", "Saturated fat (g)", self.saturated_fat),
+            ("Carbs", "Carbohydrates (g)", self.carbohydrates),
+            ("Sugar", "Sugars (g)", self.sugars),
+            ("Fiber", "Fiber (g)", self.fiber),
+            ("Prot.", "Proteins (g)", self.proteins),
+            ("Salt", "Salt (g)", self.salt),
+        ]
+    }
+
+    /// The factor to get from 100 g (or 100 ml) to the amount of one package
+    /// with the `quantity` of the product, e.g. "500 g". Returns `None` if the
+    /// quantity isn't a weight or volume.
+    pub fn package_factor(quantity: &str) -> Option<f32> {
+        let amount = Amount::parse(quantity)?;
+        let base = amount.convert_to(Unit::Gram).or_else(|| amount.convert_to(Unit::Millilitre))?;
+        Some(base.value() as f32 / 100.0)
+    }
+}
+
+json_column!(Nutrition);
+
+fn format_value(value: Option<f32>, factor: f32) -> String {
+    match value.map(|v| v * factor) {
+        Some(v) if v >= 100.0 => format!("{v:.0}"),
+        Some(v) => format!("{v:.1}"),
+        None => "-".to_string(),
+    }
+}
+
+/// Shows the nutrition facts per 100 g and, if the `quantity` can be parsed,
+/// per package.
+#[component]
+pub fn NutritionView(nutrition: Nutrition, quantity: String) -> impl IntoView {
+    let columns = nutrition.columns();
+    let row = move |label: String, factor: f32| {
+        view! {
+            <tr>
+                <th>{ label }</th>
+                {
+                    columns
+                        .into_iter()
+                        .map(|(_, _, value)| view! { <td>{ format_value(value, factor) }</td> })
+                        .collect_view()
+                }
+            </tr>
+        }
+    };
+
+    view! {
+        <table class="nutrition sub-info" hidden=nutrition.is_empty()>
+            <tr>
+                <th></th>
+                {
+                    columns
+                        .into_iter()
+                        .map(|(short, long, _)| view! { <th title=long>{ short }</th> })
+                        .collect_view()
+                }
+            </tr>
+            { row("100 g".to_string(), 1.0) }
+            { Nutrition::package_factor(&quantity).map(|factor| row(quantity.clone(), factor)) }
+        </table>
+    }
+}
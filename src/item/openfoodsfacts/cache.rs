@@ -0,0 +1,148 @@
+//! SQLite-backed cache for the responses of the [`OpenFacts`] databases.
+//!
+//! The lifetime of the entries can be configured with the environment variables
+//! `OPENFOODFACTS_CACHE_TTL` (found products) and
+//! `OPENFOODFACTS_NOT_FOUND_TTL` (unknown barcodes), both in hours.
+
+use super::{CacheStats, OpenFacts};
+use crate::{
+    barcode_scanner::Barcode,
+    db::DB,
+    error::{Error, Result},
+};
+use leptos::logging;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+lazy_static::lazy_static! {
+    static ref TTL: i64 = ttl_from_env("OPENFOODFACTS_CACHE_TTL", 7 * 24);
+    static ref NOT_FOUND_TTL: i64 = ttl_from_env("OPENFOODFACTS_NOT_FOUND_TTL", 24);
+}
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the TTL in seconds.
+fn ttl_from_env(var: &str, default_hours: i64) -> i64 {
+    let hours = std::env::var(var).ok().and_then(|h| h.parse().ok()).unwrap_or(default_hours);
+    hours * 60 * 60
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Like [`OpenFacts::request_with_barcode`], but uses the cached response if
+/// it hasn't expired yet. If `refresh` is set, the cache is bypassed and the
+/// entry is replaced.
+pub async fn request_with_barcode(
+    api: OpenFacts,
+    barcode: Barcode,
+    refresh: bool,
+    db: &DB,
+) -> Result<serde_json::Value> {
+    if !refresh {
+        if let Some(entry) = lookup(api, barcode, db).await? {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            return entry.ok_or(Error::DidntFindProduct);
+        }
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+
+    match api.request_with_barcode(barcode).await {
+        Ok(product) => {
+            store(api, barcode, Some(&product), db).await?;
+            Ok(product)
+        },
+        Err(Error::DidntFindProduct) => {
+            store(api, barcode, None, db).await?;
+            Err(Error::DidntFindProduct)
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// `Some(None)` means the product wasn't found the last time.
+async fn lookup(
+    api: OpenFacts,
+    barcode: Barcode,
+    db: &DB,
+) -> Result<Option<Option<serde_json::Value>>> {
+    let mut conn = db.connection().await?;
+    let Some(row) = sqlx::query!(
+        "SELECT product, fetched_at FROM openfoodfacts_cache WHERE provider = ? AND barcode = ?",
+        api.name,
+        barcode
+    )
+    .fetch_optional(conn.as_mut())
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let ttl = if row.product.is_some() { *TTL } else { *NOT_FOUND_TTL };
+    if row.fetched_at + ttl < now() {
+        logging::log!("{} cache entry for {barcode} expired", api.name);
+        return Ok(None);
+    }
+    Ok(Some(row.product.as_deref().map(serde_json::from_str).transpose()?))
+}
+
+async fn store(
+    api: OpenFacts,
+    barcode: Barcode,
+    product: Option<&serde_json::Value>,
+    db: &DB,
+) -> Result<()> {
+    let product = product.map(serde_json::to_string).transpose()?;
+    let fetched_at = now();
+    let mut conn = db.connection().await?;
+    sqlx::query!(
+        r#"INSERT OR REPLACE INTO openfoodfacts_cache(provider, barcode, product, fetched_at)
+        VALUES ( ?, ?, ?, ? )"#,
+        api.name,
+        barcode,
+        product,
+        fetched_at
+    )
+    .execute(conn.as_mut())
+    .await?;
+    Ok(())
+}
+
+/// Forgets the entry of `barcode`, e.g. after the product was added to `api`.
+pub async fn remove(api: OpenFacts, barcode: Barcode, db: &DB) -> Result<()> {
+    let mut conn = db.connection().await?;
+    sqlx::query!(
+        "DELETE FROM openfoodfacts_cache WHERE provider = ? AND barcode = ?",
+        api.name,
+        barcode
+    )
+    .execute(conn.as_mut())
+    .await?;
+    Ok(())
+}
+
+pub async fn stats(db: &DB) -> Result<CacheStats> {
+    let mut conn = db.connection().await?;
+    let row = sqlx::query!(
+        r#"SELECT COUNT(*) as "entries!: i64", COUNT(product) as "found!: i64"
+        FROM openfoodfacts_cache"#
+    )
+    .fetch_one(conn.as_mut())
+    .await?;
+    Ok(CacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        entries: row.entries.max(0) as u64,
+        not_found_entries: (row.entries - row.found).max(0) as u64,
+    })
+}
+
+/// Returns the number of removed entries.
+pub async fn clear(db: &DB) -> Result<u64> {
+    let mut conn = db.connection().await?;
+    Ok(sqlx::query!("DELETE FROM openfoodfacts_cache")
+        .execute(conn.as_mut())
+        .await?
+        .rows_affected())
+}
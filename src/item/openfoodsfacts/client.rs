@@ -0,0 +1,115 @@
+//! The HTTP client for the [`OpenFacts`] APIs.
+//!
+//! Requests time out, transient failures are retried with an increasing delay
+//! and every instance is asked at most `OPENFOODFACTS_REQUESTS_PER_MINUTE`
+//! times per minute (default: 100, the limit of the public product API).
+
+use super::OpenFacts;
+use crate::error::{Error, Result};
+use reqwest::{header, Response, StatusCode};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// OpenFoodFacts asks every app to identify itself.
+const USER_AGENT: &str =
+    concat!("ShoppingList/", env!("CARGO_PKG_VERSION"), " (https://github.com/Qwox0/shopping_list)");
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request is sent at most `1 + MAX_RETRIES` times.
+const MAX_RETRIES: u32 = 2;
+/// The delay before the first retry. It doubles with every retry.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// A longer `Retry-After` isn't waited for, the user should rather retry later.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+lazy_static::lazy_static! {
+    pub static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(TIMEOUT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .expect("could build the HTTP client");
+
+    /// The minimum time between two requests to the same instance.
+    static ref MIN_INTERVAL: Duration = {
+        let per_minute = std::env::var("OPENFOODFACTS_REQUESTS_PER_MINUTE")
+            .ok()
+            .and_then(|n| n.parse::<u32>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(100);
+        Duration::from_secs(60) / per_minute
+    };
+
+    /// The earliest time of the next request by [`OpenFacts::name`].
+    static ref NEXT_REQUEST: Mutex<HashMap<&'static str, Instant>> = Mutex::default();
+}
+
+/// Distinguishes timeouts and unreachable servers from other failures.
+pub fn classify(err: reqwest::Error) -> Error {
+    if err.is_timeout() {
+        Error::Timeout
+    } else if err.is_connect() {
+        Error::Network
+    } else {
+        Error::Reqwest(err)
+    }
+}
+
+/// Waits until the next request to `api` doesn't exceed the rate limit.
+async fn wait_for_slot(api: OpenFacts) {
+    let wait = {
+        let mut next_request = NEXT_REQUEST.lock().unwrap();
+        let now = Instant::now();
+        let slot = next_request.get(api.name).copied().unwrap_or(now).max(now);
+        next_request.insert(api.name, slot + *MIN_INTERVAL);
+        slot - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Maps the error statuses of the product API to [`Error`]s.
+fn check_status(res: Response) -> Result<Response> {
+    match res.status() {
+        s if s.is_success() => Ok(res),
+        StatusCode::NOT_FOUND => Err(Error::DidntFindProduct),
+        StatusCode::TOO_MANY_REQUESTS => Err(Error::RateLimited),
+        s if s.is_server_error() => Err(Error::ServiceUnavailable(s.as_u16())),
+        s => Err(Error::UnexpectedStatus(s.as_u16())),
+    }
+}
+
+/// The `Retry-After` header in seconds. HTTP dates aren't supported.
+fn retry_after(res: &Response) -> Option<Duration> {
+    let secs = res.headers().get(header::RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// GETs `url` from `api`. Timeouts, network errors, "429 Too Many Requests"
+/// and server errors are retried.
+pub async fn get(api: OpenFacts, url: &str, query: &[(&str, String)]) -> Result<Response> {
+    let mut retries = 0;
+    loop {
+        wait_for_slot(api).await;
+        let res = CLIENT.get(url).query(query).send().await.map_err(classify);
+        let delay = match &res {
+            Ok(res) => retry_after(res),
+            Err(_) => None,
+        }
+        .unwrap_or(RETRY_DELAY * 2_u32.pow(retries));
+        match res.and_then(check_status) {
+            Err(err) if err.is_transient() && retries < MAX_RETRIES && delay <= MAX_RETRY_AFTER => {
+                leptos::logging::warn!("{} failed ({}), retrying in {:?}", api.name, err, delay);
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            },
+            res => return res,
+        }
+    }
+}
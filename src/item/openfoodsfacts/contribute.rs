@@ -0,0 +1,277 @@
+//! Submits products which OpenFoodFacts didn't know to its write API.
+//!
+//! Contributions are queued in the database and sent by
+//! [`run_contribution_worker`], which retries submissions that failed
+//! transiently, e.g. offline, with an increasing delay. The account is configured with the environment variables
+//! `OPENFOODFACTS_USER` and `OPENFOODFACTS_PASSWORD`; without them nothing is
+//! queued.
+
+#[cfg(feature = "ssr")]
+use super::{
+    cache,
+    client::{classify, CLIENT},
+    OpenFacts,
+};
+use crate::{barcode_scanner::Barcode, language::Language, util::json_column};
+#[cfg(feature = "ssr")]
+use crate::{db::DB, error::Error};
+#[cfg(feature = "ssr")]
+use leptos::logging;
+use leptos::{server, ServerFnError};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use std::time::Duration;
+
+/// How often the worker checks for due contributions.
+#[cfg(feature = "ssr")]
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// After this many failed attempts a contribution is given up.
+#[cfg(feature = "ssr")]
+const MAX_ATTEMPTS: i64 = 10;
+
+/// A product typed in by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub barcode: Barcode,
+    pub name: String,
+    /// The language of `name`.
+    pub lang: Language,
+    pub brands: String,
+    pub quantity: String,
+    /// A `data:` URL of a photo of the front of the product.
+    pub photo: Option<String>,
+}
+
+json_column!(Contribution);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContributionStatus {
+    Pending,
+    Done,
+    /// Rejected or gave up after [`MAX_ATTEMPTS`].
+    Failed,
+}
+
+impl ContributionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContributionStatus::Pending => "pending",
+            ContributionStatus::Done => "done",
+            ContributionStatus::Failed => "failed",
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+struct Credentials {
+    user: String,
+    password: String,
+}
+
+#[cfg(feature = "ssr")]
+fn credentials() -> Option<Credentials> {
+    let user = std::env::var("OPENFOODFACTS_USER").ok().filter(|u| !u.is_empty())?;
+    let password = std::env::var("OPENFOODFACTS_PASSWORD").ok()?;
+    Some(Credentials { user, password })
+}
+
+#[cfg(feature = "ssr")]
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// The delay after the `attempts`th failed attempt: 1 min, 2 min, 4 min, …, at
+/// most a day.
+#[cfg(feature = "ssr")]
+fn backoff(attempts: i64) -> i64 {
+    (60_i64 << attempts.clamp(0, 20)).min(24 * 60 * 60)
+}
+
+/// The response of the write API.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct WriteResponse {
+    status: i64,
+    #[serde(default)]
+    status_verbose: String,
+}
+
+#[cfg(feature = "ssr")]
+impl WriteResponse {
+    async fn check(res: reqwest::Response) -> Result<(), Error> {
+        let status = res.status();
+        if !status.is_success() {
+            return Err(Error::UnexpectedStatus(status.as_u16()));
+        }
+        match res.json::<WriteResponse>().await.map_err(classify)? {
+            WriteResponse { status: 1, .. } => Ok(()),
+            WriteResponse { status_verbose, .. } => {
+                Err(Error::ContributionRejected(status_verbose))
+            },
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Contribution {
+    /// Returns the id of the queued contribution.
+    pub async fn enqueue(self, db: &DB) -> Result<i64, Error> {
+        if credentials().is_none() {
+            return Err(Error::ContributionsDisabled);
+        }
+        let next_attempt = now();
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!(
+            "INSERT INTO contribution(contribution, next_attempt) VALUES ( ?, ? )",
+            self,
+            next_attempt
+        )
+        .execute(conn.as_mut())
+        .await?
+        .last_insert_rowid())
+    }
+
+    /// Sends the product data and the photo to the write API of
+    /// [`OpenFacts::FOOD`].
+    async fn submit(&self, credentials: &Credentials) -> Result<(), Error> {
+        let base_url = OpenFacts::FOOD.base_url();
+        let base_url = base_url.trim_end_matches('/');
+        let code = self.barcode.openfoodfacts_code();
+        let lang = self.lang.code();
+
+        let fields = [
+            ("code", code.as_str()),
+            ("user_id", &credentials.user),
+            ("password", &credentials.password),
+            ("lang", lang),
+            ("product_name", &self.name),
+            ("brands", &self.brands),
+            ("quantity", &self.quantity),
+        ];
+        let url = format!("{base_url}/cgi/product_jqm2.pl");
+        let res = CLIENT.post(url).form(&fields).send().await.map_err(classify)?;
+        WriteResponse::check(res).await?;
+
+        let Some(photo) = &self.photo else { return Ok(()) };
+        let photo = data_url::DataUrl::process(photo).map_err(|_| Error::InvalidPhoto)?;
+        let (type_, subtype) = (&photo.mime_type().type_, &photo.mime_type().subtype);
+        if type_ != "image" {
+            return Err(Error::InvalidPhoto);
+        }
+        let mime = format!("{type_}/{subtype}");
+        let file_name = format!("front.{subtype}");
+        let (bytes, _) = photo.decode_to_vec().map_err(|_| Error::InvalidPhoto)?;
+        let image = reqwest::multipart::Part::bytes(bytes)
+            .file_name(file_name)
+            .mime_str(&mime)
+            .map_err(|_| Error::InvalidPhoto)?;
+        let imagefield = format!("front_{lang}");
+        let form = reqwest::multipart::Form::new()
+            .text("code", code)
+            .text("user_id", credentials.user.clone())
+            .text("password", credentials.password.clone())
+            .text("imagefield", imagefield.clone())
+            .part(format!("imgupload_{imagefield}"), image);
+        let url = format!("{base_url}/cgi/product_image_upload.pl");
+        let res = CLIENT.post(url).multipart(form).send().await.map_err(classify)?;
+        WriteResponse::check(res).await
+    }
+}
+
+/// Submits all due contributions once. Returns the number of successful
+/// submissions.
+#[cfg(feature = "ssr")]
+pub async fn process_due(db: &DB) -> Result<usize, Error> {
+    let Some(credentials) = credentials() else { return Ok(0) };
+    let now = now();
+    let pending = ContributionStatus::Pending.as_str();
+    let due = {
+        let mut conn = db.connection().await?;
+        sqlx::query!(
+            r#"SELECT id, contribution as "contribution: Contribution", attempts
+            FROM contribution WHERE status = ? AND next_attempt <= ?"#,
+            pending,
+            now
+        )
+        .fetch_all(conn.as_mut())
+        .await?
+    };
+
+    let mut submitted = 0;
+    for row in due {
+        let result = row.contribution.submit(&credentials).await;
+        let mut conn = db.connection().await?;
+        match result {
+            Ok(()) => {
+                let done = ContributionStatus::Done.as_str();
+                sqlx::query!(
+                    "UPDATE contribution SET status = ?, last_error = NULL WHERE id = ?",
+                    done,
+                    row.id
+                )
+                .execute(conn.as_mut())
+                .await?;
+                // The product isn't unknown anymore.
+                cache::remove(OpenFacts::FOOD, row.contribution.barcode, db).await?;
+                logging::log!("contributed {} to OpenFoodFacts", row.contribution.barcode);
+                submitted += 1;
+            },
+            Err(err) => {
+                logging::error!("ERROR (contribution {}): {}", row.id, err);
+                let attempts = row.attempts + 1;
+                // Rejected contributions and invalid photos won't succeed later.
+                let status = if attempts >= MAX_ATTEMPTS || !err.is_transient() {
+                    ContributionStatus::Failed
+                } else {
+                    ContributionStatus::Pending
+                };
+                let status = status.as_str();
+                let next_attempt = now + backoff(attempts);
+                let err = err.to_string();
+                sqlx::query!(
+                    r#"UPDATE contribution
+                    SET attempts = ?, status = ?, next_attempt = ?, last_error = ?
+                    WHERE id = ?"#,
+                    attempts,
+                    status,
+                    next_attempt,
+                    err,
+                    row.id
+                )
+                .execute(conn.as_mut())
+                .await?;
+            },
+        }
+    }
+    Ok(submitted)
+}
+
+/// Periodically submits the queued contributions. Returns immediately if no
+/// account is configured.
+#[cfg(feature = "ssr")]
+pub async fn run_contribution_worker() {
+    if credentials().is_none() {
+        logging::log!("no OpenFoodFacts account configured, contributions are disabled");
+        return;
+    }
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = process_due(&crate::db::MY_DB).await {
+            logging::error!("ERROR (contribution worker): {}", err);
+        }
+    }
+}
+
+/// Whether an OpenFoodFacts account is configured on the server.
+#[server]
+pub async fn contributions_enabled() -> Result<bool, ServerFnError> {
+    Ok(credentials().is_some())
+}
+
+/// Queues the `contribution`. Returns its id.
+#[server(input = Json)]
+pub async fn contribute_product(contribution: Contribution) -> Result<i64, ServerFnError> {
+    Ok(contribution.enqueue(&crate::db::MY_DB).await?)
+}
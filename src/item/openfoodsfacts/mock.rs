@@ -0,0 +1,478 @@
+//! An in-process stand-in for the OpenFoodFacts v2 API which serves fixture
+//! responses and records writes, so the barcode lookups and contributions can
+//! be tested without the internet.
+//! [`init`] also points [`crate::db::MY_DB`] to a fresh database.
+
+use super::{
+    contribute::{self, Contribution},
+    LookupError, OpenFacts, OpenFoodFactsProduct,
+};
+use crate::{
+    barcode_scanner::{Barcode, Gs1Data, OptionBarcode},
+    error::Error,
+    image_store::{self, thumb_srcset},
+    item::{
+        catalog::{self, Format, ImportOptions},
+        data::Item,
+        package::Package,
+        provider::{self, LocalCatalog, ProductProvider, Provider},
+        scores::{Level, NutriScore},
+        server_functions::add_item_from_barcode,
+        variant_data::NewVariant,
+    },
+    language::Language,
+    scan::scan_item,
+};
+use axum::{
+    body::Bytes,
+    extract::{Form, Path, Query},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use sqlx::{Connection, Executor, SqliteConnection};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+/// The response for [`FIXTURE_BARCODE`].
+const FIXTURE: &str = include_str!("../../../test.json");
+/// The image URLs of [`FIXTURE`] are pointed to the mock, so storing the images
+/// of the added items doesn't go to the internet.
+const FIXTURE_IMAGE_HOST: &str = "https://images.openfoodfacts.org";
+const FIXTURE_BARCODE: &str = "4002674044119";
+const UNKNOWN_BARCODE: &str = "4000000000006";
+/// The mock always answers with "429 Too Many Requests" for this barcode.
+const RATE_LIMITED_BARCODE: &str = "4000000000013";
+/// Unknown to the mock, but in the local catalog of [`test_local_catalog`].
+const CATALOG_BARCODE: &str = "4000000000020";
+const CONTRIBUTED_BARCODE: &str = "4000000000037";
+/// The write API rejects contributions for this barcode.
+const REJECTED_BARCODE: &str = "4000000000044";
+/// The first request for this barcode fails with "503 Service Unavailable".
+const FLAKY_BARCODE: &str = "4000000000051";
+/// Unknown to the mock, but imported by [`test_import_catalog`].
+const IMPORTED_BARCODES: [&str; 3] = ["4000000000068", "4000000000075", "4000000000082"];
+
+static MOCK_URL: OnceLock<String> = OnceLock::new();
+/// The forms posted to the write API.
+static WRITES: Mutex<Vec<HashMap<String, String>>> = Mutex::new(Vec::new());
+/// The number of uploaded images.
+static IMAGE_UPLOADS: Mutex<usize> = Mutex::new(0);
+/// The number of requests for [`FLAKY_BARCODE`].
+static FLAKY_REQUESTS: Mutex<usize> = Mutex::new(0);
+
+#[derive(Deserialize)]
+struct ProductQuery {
+    fields: Option<String>,
+}
+
+/// [`FIXTURE`] with the images served by the mock.
+fn fixture() -> serde_json::Value {
+    let fixture = FIXTURE.replace(FIXTURE_IMAGE_HOST, MOCK_URL.get().unwrap());
+    serde_json::from_str(&fixture).unwrap()
+}
+
+/// Like the v2 API, only the requested `fields` of the product are returned.
+async fn product(
+    Path(code): Path<String>,
+    Query(ProductQuery { fields }): Query<ProductQuery>,
+) -> impl IntoResponse {
+    let json = [(header::CONTENT_TYPE, "application/json")];
+    if code == RATE_LIMITED_BARCODE {
+        return (StatusCode::TOO_MANY_REQUESTS, json, String::new());
+    }
+    if code == FLAKY_BARCODE {
+        let mut requests = FLAKY_REQUESTS.lock().unwrap();
+        *requests += 1;
+        if *requests == 1 {
+            return (StatusCode::SERVICE_UNAVAILABLE, json, String::new());
+        }
+        let body = serde_json::json!({
+            "code": code,
+            "status": 1,
+            "product": { "product_name": "Wackelpudding" },
+        });
+        return (StatusCode::OK, json, body.to_string());
+    }
+    if code != FIXTURE_BARCODE {
+        let body = serde_json::json!({
+            "code": code,
+            "status": 0,
+            "status_verbose": "product not found",
+        });
+        return (StatusCode::NOT_FOUND, json, body.to_string());
+    }
+
+    let mut response = fixture();
+    if let (Some(fields), Some(product)) = (fields, response["product"].as_object_mut()) {
+        let fields = fields.split(',').collect::<Vec<_>>();
+        product.retain(|key, _| fields.contains(&key.as_str()));
+    }
+    (StatusCode::OK, json, response.to_string())
+}
+
+async fn write_product(Form(form): Form<HashMap<String, String>>) -> impl IntoResponse {
+    let json = [(header::CONTENT_TYPE, "application/json")];
+    let rejected = form.get("code").map(String::as_str) == Some(REJECTED_BARCODE);
+    WRITES.lock().unwrap().push(form);
+    let body = if rejected {
+        serde_json::json!({ "status": 0, "status_verbose": "no code or invalid code" })
+    } else {
+        serde_json::json!({ "status": 1, "status_verbose": "fields saved" })
+    };
+    (StatusCode::OK, json, body.to_string())
+}
+
+/// A generated 300×150 PNG.
+async fn product_image() -> impl IntoResponse {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(300, 150).write_to(&mut png, image::ImageFormat::Png).unwrap();
+    ([(header::CONTENT_TYPE, "image/png")], png.into_inner())
+}
+
+/// Not an image, e.g. an error page.
+async fn broken_image() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "image/png")], "<html>Oops</html>")
+}
+
+async fn upload_image(_image: Bytes) -> impl IntoResponse {
+    *IMAGE_UPLOADS.lock().unwrap() += 1;
+    let json = [(header::CONTENT_TYPE, "application/json")];
+    (StatusCode::OK, json, serde_json::json!({ "status": 1 }).to_string())
+}
+
+/// Runs `f` on a new thread with its own runtime, because the tests already
+/// run inside a runtime.
+fn run_blocking<F: std::future::Future + Send + 'static>(f: F)
+where F::Output: Send + 'static {
+    std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(f)
+    });
+}
+
+fn init_db() {
+    let path = std::env::temp_dir().join(format!("shopping_list_test_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+    std::env::set_var("DATABASE_URL", &url);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    run_blocking(async move {
+        let mut conn = SqliteConnection::connect(&url).await.unwrap();
+        conn.execute(include_str!("../../../data/create_table.sql")).await.unwrap();
+        tx.send(()).unwrap();
+    });
+    rx.recv().unwrap();
+}
+
+/// Starts the mock server and the test database once per test binary.
+fn init() {
+    MOCK_URL.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        run_blocking(async move {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            tx.send(format!("http://{}", listener.local_addr().unwrap())).unwrap();
+            let app = Router::new()
+                .route("/api/v2/product/:code", get(product))
+                .route("/cgi/product_jqm2.pl", post(write_product))
+                .route("/cgi/product_image_upload.pl", post(upload_image))
+                .route("/product-image.png", get(product_image))
+                .route("/broken-image.png", get(broken_image))
+                .route("/images/*path", get(product_image));
+            axum::serve(listener, app).await.unwrap();
+        });
+        let url = rx.recv().unwrap();
+        OpenFacts::FOOD.set_base_url(url.clone());
+        std::env::set_var("OPENFOODFACTS_USER", "test");
+        std::env::set_var("OPENFOODFACTS_PASSWORD", "secret");
+        let image_dir = format!("shopping_list_images_{}", std::process::id());
+        std::env::set_var("IMAGE_DIR", std::env::temp_dir().join(image_dir));
+        // The tests share the rate limit of the mock.
+        std::env::set_var("OPENFOODFACTS_REQUESTS_PER_MINUTE", "60000");
+        // Don't ask the real Open Beauty Facts and Open Pet Food Facts.
+        provider::set_providers(vec![
+            Provider::LocalCatalog(LocalCatalog),
+            Provider::OpenFacts(OpenFacts::FOOD),
+        ]);
+        init_db();
+        url
+    });
+}
+
+fn barcode(code: &str) -> Barcode {
+    code.parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_request_product() {
+    init();
+    let product =
+        OpenFoodFactsProduct::request_with_barcode(barcode(FIXTURE_BARCODE), Language::German)
+            .await
+            .unwrap();
+    assert_eq!(product.product_name, "Paprika Rosenscharf");
+    assert_eq!(product.name_lang, "de");
+    assert_eq!(product.brands, "Ostmann");
+
+    let json = OpenFacts::FOOD.request_with_barcode(barcode(FIXTURE_BARCODE)).await.unwrap();
+    assert!(json.get("product_name").is_some());
+    assert!(json.get("_keywords").is_none(), "only the requested fields are returned");
+}
+
+#[tokio::test]
+async fn test_request_unknown_product() {
+    init();
+    let barcode = barcode(UNKNOWN_BARCODE);
+    let res = OpenFacts::FOOD.request_with_barcode(barcode).await;
+    assert!(matches!(res, Err(Error::DidntFindProduct)));
+    let lang = Language::default();
+    assert!(OpenFoodFactsProduct::request_with_barcode(barcode, lang).await.is_err());
+    assert!(NewVariant::from_barcode(barcode, lang).await.is_err());
+    assert!(add_item_from_barcode(barcode, lang).await.is_err());
+}
+
+#[test]
+fn test_localized_name_fallback() {
+    let product = serde_json::json!({
+        "lang": "fr",
+        "product_name": "Paprika doux",
+        "product_name_en": "",
+        "generic_name_de": "Gewürz",
+    });
+    let product = OpenFoodFactsProduct::from_json(product, Language::English).unwrap();
+    assert_eq!((product.product_name.as_str(), product.name_lang.as_str()), ("Paprika doux", "fr"));
+    assert_eq!(product.generic_name, "Gewürz");
+}
+
+#[tokio::test]
+async fn test_rate_limited() {
+    init();
+    let res = OpenFacts::FOOD.request_with_barcode(barcode(RATE_LIMITED_BARCODE)).await;
+    assert!(matches!(res, Err(Error::RateLimited)));
+}
+
+#[tokio::test]
+async fn test_retry_after_server_error() {
+    init();
+    let json = OpenFacts::FOOD.request_with_barcode(barcode(FLAKY_BARCODE)).await.unwrap();
+    assert_eq!(json["product_name"], "Wackelpudding");
+    assert_eq!(*FLAKY_REQUESTS.lock().unwrap(), 2);
+}
+
+#[test]
+fn test_lookup_error_roundtrip() {
+    for err in [
+        LookupError::NotFound,
+        LookupError::RateLimited,
+        LookupError::Timeout,
+        LookupError::Offline,
+        LookupError::Unavailable,
+        LookupError::Other("no: such table".to_string()),
+    ] {
+        assert_eq!(err.to_string().parse::<LookupError>().unwrap(), err);
+    }
+    assert!(LookupError::from(&Error::Network).is_retryable());
+    assert!(!LookupError::from(&Error::DidntFindProduct).is_retryable());
+}
+
+#[tokio::test]
+async fn test_variant_from_barcode() {
+    init();
+    let barcode = barcode(FIXTURE_BARCODE);
+    let variant = NewVariant::from_barcode(barcode, Language::English).await.unwrap();
+    assert_eq!(variant.name, "Ostmann Rosenpaprika");
+    assert_eq!(variant.name_lang, "en");
+    assert_eq!(variant.quantity, "35g");
+    assert_eq!(variant.nutrition.fat, Some(13.0));
+    assert_eq!(variant.nutrition.saturated_fat, Some(2.1));
+    assert_eq!(variant.scores.nutriscore, Some(NutriScore::C));
+    assert_eq!(variant.scores.nova_group, None);
+    assert_eq!(variant.scores.sugars, Some(Level::High));
+    assert_eq!(variant.barcode, OptionBarcode::some(barcode));
+}
+
+#[tokio::test]
+async fn test_add_item_from_barcode() {
+    init();
+    let barcode = barcode(FIXTURE_BARCODE);
+    let id = add_item_from_barcode(barcode, Language::German).await.unwrap();
+    let item = Item::select_by_id(id, &crate::db::MY_DB).await.unwrap();
+    assert_eq!(item.name(), "Paprika Rosenscharf");
+    assert_eq!(item.variants[0].name_lang, "de");
+    assert_eq!(item.variants[0].barcode, OptionBarcode::some(barcode));
+}
+
+#[tokio::test]
+async fn test_scan_package() {
+    init();
+    let data = Gs1Data::parse("(01)04002674044119(17)250630(10)LOT-7").unwrap();
+    let package = Package::from(&data);
+    let barcode = data.gtin.unwrap();
+    let scanned = scan_item(barcode, package.clone(), Language::German).await.unwrap();
+    let item = Item::select_by_id(scanned.item_id, &crate::db::MY_DB).await.unwrap();
+    let variant = item.variants.iter().find(|v| v.barcode == OptionBarcode::some(barcode));
+    assert_eq!(variant.unwrap().package, package);
+    assert_eq!(package.batch, "LOT-7");
+}
+
+#[tokio::test]
+async fn test_local_catalog() {
+    init();
+    let barcode = barcode(CATALOG_BARCODE);
+    let product = serde_json::json!({ "product_name": "Katzenfutter", "brands": "Miau" });
+    let product = product.to_string();
+    let mut conn = crate::db::MY_DB.connection().await.unwrap();
+    sqlx::query!("INSERT INTO catalog(barcode, product) VALUES ( ?, ? )", barcode, product)
+        .execute(conn.as_mut())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        OpenFacts::FOOD.request_with_barcode(barcode).await,
+        Err(Error::DidntFindProduct)
+    ));
+    let variant = NewVariant::from_barcode(barcode, Language::English).await.unwrap();
+    assert_eq!(variant.name, "Katzenfutter");
+    assert_eq!(variant.brands, "Miau");
+}
+
+#[tokio::test]
+async fn test_contribute_product() {
+    init();
+    let db = &crate::db::MY_DB;
+    let contribution = |code: &str| Contribution {
+        barcode: barcode(code),
+        name: "Hafermilch".to_string(),
+        lang: Language::German,
+        brands: "Oatly".to_string(),
+        quantity: "1 l".to_string(),
+        photo: Some("data:image/png;base64,iVBORw0KGgo=".to_string()),
+    };
+    contribution(CONTRIBUTED_BARCODE).enqueue(db).await.unwrap();
+    let rejected = contribution(REJECTED_BARCODE).enqueue(db).await.unwrap();
+
+    assert_eq!(contribute::process_due(db).await.unwrap(), 1);
+    let write = WRITES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|w| w.get("code").map(String::as_str) == Some(CONTRIBUTED_BARCODE))
+        .cloned()
+        .unwrap();
+    assert_eq!(write["product_name"], "Hafermilch");
+    assert_eq!(write["lang"], "de");
+    assert_eq!(write["user_id"], "test");
+    assert_eq!(*IMAGE_UPLOADS.lock().unwrap(), 1);
+
+    let mut conn = db.connection().await.unwrap();
+    let row = sqlx::query!(
+        "SELECT status, attempts, last_error FROM contribution WHERE id = ?",
+        rejected
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .unwrap();
+    assert_eq!((row.status.as_str(), row.attempts), ("failed", 1));
+    assert!(row.last_error.unwrap().contains("invalid code"));
+
+    // The rejected contribution isn't retried.
+    assert_eq!(contribute::process_due(db).await.unwrap(), 0);
+    let writes = WRITES.lock().unwrap();
+    let rejected_writes = writes.iter().filter(|w| w["code"] == REJECTED_BARCODE).count();
+    assert_eq!(rejected_writes, 1);
+}
+
+#[tokio::test]
+async fn test_import_catalog() {
+    init();
+    let db = &crate::db::MY_DB;
+    let [german, french, csv] = IMPORTED_BARCODES;
+    let fixture = fixture()["product"].clone();
+    let document = |code: &str, country: &str| {
+        let mut product = fixture.clone();
+        product["code"] = code.into();
+        product["countries_tags"] = serde_json::json!([country]);
+        product.to_string()
+    };
+    let jsonl = [document(german, "en:germany"), document(french, "en:france"), "{".to_string()];
+    let mut options = ImportOptions::default();
+    options.add_country("Germany");
+
+    let stats = catalog::import(jsonl.join("\n").as_bytes(), Format::Jsonl, &options, db).await;
+    let stats = stats.unwrap();
+    assert_eq!((stats.imported, stats.filtered, stats.invalid), (1, 1, 1));
+    let variant = NewVariant::from_barcode(barcode(german), Language::German).await.unwrap();
+    assert_eq!(variant.name, "Paprika Rosenscharf");
+    assert_eq!(variant.nutrition.fat, Some(13.0));
+    let json = LocalCatalog.request_with_barcode(barcode(german), false, db).await.unwrap();
+    assert!(json.get("_keywords").is_none(), "only the requested fields are stored");
+    assert!(NewVariant::from_barcode(barcode(french), Language::German).await.is_err());
+
+    let tsv = format!(
+        "code\tproduct_name\tbrands\tcountries_tags\tallergens\tfat_100g\n\
+         {csv}\tHaferdrink\tOatly\ten:sweden,en:germany\ten:gluten\t1.5\n\
+         123\tNo EAN\t\ten:germany\t\t\n"
+    );
+    let stats = catalog::import(tsv.as_bytes(), Format::Csv, &options, db).await.unwrap();
+    assert_eq!((stats.imported, stats.filtered, stats.invalid), (1, 0, 1));
+    let variant = NewVariant::from_barcode(barcode(csv), Language::German).await.unwrap();
+    assert_eq!((variant.name.as_str(), variant.brands.as_str()), ("Haferdrink", "Oatly"));
+    assert_eq!(variant.nutrition.fat, Some(1.5));
+    assert_eq!(variant.dietary.allergens, ["en:gluten"]);
+}
+
+async fn variant_image_urls(id: i64) -> (Option<String>, Option<String>) {
+    let mut conn = crate::db::MY_DB.connection().await.unwrap();
+    let row = sqlx::query!("SELECT img_url, thumb_url FROM item_variant WHERE id = ?", id)
+        .fetch_one(conn.as_mut())
+        .await
+        .unwrap();
+    (row.img_url, row.thumb_url)
+}
+
+#[tokio::test]
+async fn test_store_remote_images() {
+    init();
+    let db = &crate::db::MY_DB;
+    let mock_url = MOCK_URL.get().unwrap();
+    let mut conn = db.connection().await.unwrap();
+    let item = sqlx::query!("INSERT INTO item(amount, completed) VALUES ( 1, FALSE )")
+        .execute(conn.as_mut())
+        .await
+        .unwrap()
+        .last_insert_rowid();
+    let mut ids = vec![];
+    for file in ["product-image.png", "missing.png", "broken-image.png"] {
+        let url = format!("{mock_url}/{file}");
+        let id = sqlx::query!(
+            r#"INSERT INTO item_variant(variant_of, name, img_url, thumb_url)
+            VALUES ( ?, 'Bild', ?, ? )"#,
+            item,
+            url,
+            url
+        )
+        .execute(conn.as_mut())
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        ids.push(id);
+    }
+    drop(conn);
+
+    image_store::store_remote_images(db).await.unwrap();
+    let (img_url, thumb_url) = variant_image_urls(ids[0]).await;
+    let (img_url, thumb_url) = (img_url.unwrap(), thumb_url.unwrap());
+    assert!(img_url.starts_with(image_store::ROUTE));
+    assert!(thumb_srcset(&thumb_url).unwrap().contains("-200.jpg 2x"));
+    let image_dir = std::path::PathBuf::from(std::env::var("IMAGE_DIR").unwrap());
+    let thumb = image::open(image_dir.join(thumb_url.rsplit('/').next().unwrap())).unwrap();
+    assert_eq!((thumb.width(), thumb.height()), (100, 50));
+
+    // The image doesn't exist anymore or isn't an image.
+    assert_eq!(variant_image_urls(ids[1]).await, (None, None));
+    assert_eq!(variant_image_urls(ids[2]).await, (None, None));
+}
@@ -0,0 +1,419 @@
+#[cfg(feature = "ssr")]
+pub mod cache;
+#[cfg(feature = "ssr")]
+pub(crate) mod client;
+pub mod contribute;
+#[cfg(all(test, feature = "ssr"))]
+mod mock;
+
+use crate::{
+    barcode_scanner::Barcode,
+    error::{Error, Result},
+    language::Language,
+};
+use leptos::{logging, server, ServerFnError, ServerFnErrorErr};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display, sync::RwLock};
+
+/// The fields of [`OpenFoodFactsProduct`] which are requested from the API.
+const FIELDS: &[&str] = &[
+    "lang",
+    "product_name",
+    "generic_name",
+    "brands",
+    "quantity",
+    "image_url",
+    "image_thumb_url",
+    "packaging",
+    "nutriments",
+    "nutriscore_grade",
+    "nova_group",
+    "nutrient_levels",
+    "allergens_tags",
+    "traces_tags",
+    "ingredients_analysis_tags",
+];
+
+/// These fields are also requested as `<field>_<lang>` for every [`Language`].
+const LOCALIZED_FIELDS: &[&str] = &["product_name", "generic_name"];
+
+/// The [`FIELDS`] and the [`LOCALIZED_FIELDS`] in every [`Language`].
+pub fn field_names() -> Vec<String> {
+    let localized = LOCALIZED_FIELDS
+        .iter()
+        .flat_map(|f| Language::ALL.map(|l| format!("{f}_{}", l.code())));
+    FIELDS.iter().map(|f| f.to_string()).chain(localized).collect()
+}
+
+fn fields() -> String {
+    field_names().join(",")
+}
+
+lazy_static::lazy_static! {
+    /// Overrides of [`OpenFacts::base_url`] by name.
+    static ref BASE_URLS: RwLock<HashMap<&'static str, String>> = RwLock::default();
+}
+
+/// A database of the Open Food Facts family. They all share the same API and
+/// product format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenFacts {
+    /// Also used to separate the cache entries.
+    pub name: &'static str,
+    /// The environment variable which overrides `default_url`.
+    url_var: &'static str,
+    default_url: &'static str,
+}
+
+impl OpenFacts {
+    pub const FOOD: OpenFacts = OpenFacts {
+        name: "openfoodfacts",
+        url_var: "OPENFOODFACTS_URL",
+        default_url: "https://world.openfoodfacts.org",
+    };
+    pub const BEAUTY: OpenFacts = OpenFacts {
+        name: "openbeautyfacts",
+        url_var: "OPENBEAUTYFACTS_URL",
+        default_url: "https://world.openbeautyfacts.org",
+    };
+    pub const PET_FOOD: OpenFacts = OpenFacts {
+        name: "openpetfoodfacts",
+        url_var: "OPENPETFOODFACTS_URL",
+        default_url: "https://world.openpetfoodfacts.org",
+    };
+    pub const ALL: [OpenFacts; 3] = [OpenFacts::FOOD, OpenFacts::BEAUTY, OpenFacts::PET_FOOD];
+
+    /// The URL of the instance. Defaults to the `url_var` environment variable
+    /// or the public instance.
+    pub fn base_url(self) -> String {
+        BASE_URLS
+            .read()
+            .unwrap()
+            .get(self.name)
+            .cloned()
+            .or_else(|| std::env::var(self.url_var).ok())
+            .unwrap_or_else(|| self.default_url.to_string())
+    }
+
+    /// Overrides the URL of the instance, e.g. to use a mock server.
+    pub fn set_base_url(self, url: impl Into<String>) {
+        BASE_URLS.write().unwrap().insert(self.name, url.into());
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenFoodFactsProduct {
+    /// Localized by [`OpenFoodFactsProduct::from_json`].
+    pub product_name: String,
+    /// The language code of `product_name`. Empty if unknown.
+    #[serde(skip)]
+    pub name_lang: String,
+    /// Localized by [`OpenFoodFactsProduct::from_json`].
+    pub generic_name: String,
+    pub brands: String,
+    pub quantity: String,
+    // product_quantity: Option<String>,
+    pub image_url: String,
+    pub image_thumb_url: String,
+    pub packaging: String,
+    pub nutriments: Nutriments,
+    /// "a" to "e"
+    pub nutriscore_grade: String,
+    pub nova_group: Option<u8>,
+    pub nutrient_levels: NutrientLevels,
+    /// e.g. "en:nuts"
+    pub allergens_tags: Vec<String>,
+    pub traces_tags: Vec<String>,
+    /// e.g. "en:non-vegan" or "en:maybe-vegetarian"
+    pub ingredients_analysis_tags: Vec<String>,
+}
+
+/// Only the `_100g` values are optional, because they are used for
+/// [`crate::item::nutrition::Nutrition`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Nutriments {
+    pub carbohydrates: f32,
+    pub carbohydrates_100g: Option<f32>,
+    pub carbohydrates_unit: String,
+    pub carbohydrates_value: f32,
+    pub energy: f32,
+    #[serde(rename = "energy-kcal")]
+    pub energy_kcal: f32,
+    #[serde(rename = "energy-kcal_100g")]
+    pub energy_kcal_100g: Option<f32>,
+    #[serde(rename = "energy-kcal_unit")]
+    pub energy_kcal_unit: String,
+    #[serde(rename = "energy-kcal_value")]
+    pub energy_kcal_value: f32,
+    #[serde(rename = "energy-kcal_value_computed")]
+    pub energy_kcal_value_computed: f32,
+    pub energy_100g: Option<f32>,
+    pub energy_unit: String,
+    pub energy_value: f32,
+    pub fat: f32,
+    pub fat_100g: Option<f32>,
+    pub fat_unit: String,
+    pub fat_value: f32,
+    pub fiber: f32,
+    pub fiber_100g: Option<f32>,
+    pub fiber_unit: String,
+    pub fiber_value: f32,
+    // #[serde(rename = "nutrition-score-fr")]
+    // nutrition_score_fr: u32,
+    // #[serde(rename = "nutrition-score-fr_100g")]
+    // nutrition_score_fr_100g: u32,
+    pub proteins: f32,
+    pub proteins_100g: Option<f32>,
+    pub proteins_unit: String,
+    pub proteins_value: f32,
+    pub salt: f32,
+    pub salt_100g: Option<f32>,
+    pub salt_unit: String,
+    pub salt_value: f32,
+    #[serde(rename = "saturated-fat")]
+    pub saturated_fat: f32,
+    #[serde(rename = "saturated-fat_100g")]
+    pub saturated_fat_100g: Option<f32>,
+    #[serde(rename = "saturated-fat_unit")]
+    pub saturated_fat_unit: String,
+    #[serde(rename = "saturated-fat_value")]
+    pub saturated_fat_value: f32,
+    pub sodium: f32,
+    pub sodium_100g: Option<f32>,
+    pub sodium_unit: String,
+    pub sodium_value: f32,
+    pub sugars: f32,
+    pub sugars_100g: Option<f32>,
+    pub sugars_unit: String,
+    pub sugars_value: f32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NutrientLevels {
+    pub fat: String,
+    pub salt: String,
+    #[serde(rename = "saturated-fat")]
+    pub saturated_fat: String,
+    pub sugars: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct OpenFoodFactsResponse {
+    code: String,
+    product: Option<serde_json::Value>,
+    status: u8,
+    status_verbose: String,
+}
+
+impl Display for OpenFoodFactsResponse {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let is_alternate = f.alternate();
+        let mut f = f.debug_struct("OpenFoodFactsResponse");
+        let f = f
+            .field("code", &format_args!("{}", self.code))
+            .field("status", &self.status)
+            .field("status_verbose", &self.status_verbose);
+        match &self.product {
+            Some(product) if is_alternate => f.field("product", &format_args!("{:#}", product)),
+            Some(product) => f.field("product", &format_args!("{}", product)),
+            None => f,
+        }
+        .finish()
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl OpenFacts {
+    /// Requests the product from the API and returns the `product` field of
+    /// the [`OpenFoodFactsResponse`]. Only the [`fields`] are requested. If you
+    /// want to parse the response use
+    /// [`OpenFoodFactsProduct::request_with_barcode`].
+    pub async fn request_with_barcode(self, barcode: Barcode) -> Result<serde_json::Value> {
+        const OK_STATUS: u8 = 1;
+
+        let base_url = self.base_url();
+        let code = barcode.openfoodfacts_code();
+        let url = format!("{}/api/v2/product/{code}", base_url.trim_end_matches('/'));
+        let res = client::get(self, &url, &[("fields", fields())]).await?;
+
+        match res.json::<OpenFoodFactsResponse>().await.map_err(client::classify)? {
+            OpenFoodFactsResponse { product: Some(p), status: OK_STATUS, .. } => Ok(p),
+            res => {
+                logging::error!("Error with {}: {}", self.name, res.status_verbose);
+                Err(Error::DidntFindProduct)
+            },
+        }
+    }
+}
+
+/// Why a product lookup failed. Unlike the [`Error`] it crosses the server
+/// function boundary, so the UI can tell the user whether retrying might help
+/// or the product has to be typed in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LookupError {
+    NotFound,
+    RateLimited,
+    Timeout,
+    /// The product database or the server couldn't be reached.
+    Offline,
+    Unavailable,
+    Other(String),
+}
+
+impl LookupError {
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, LookupError::NotFound | LookupError::Other(_))
+    }
+
+    /// A message for the user.
+    pub fn message(&self) -> &str {
+        match self {
+            LookupError::NotFound => "Unknown product",
+            LookupError::RateLimited => "Too many lookups, try again in a minute",
+            LookupError::Timeout => "The product database took too long to answer",
+            LookupError::Offline => "Couldn't reach the product database",
+            LookupError::Unavailable => "The product database is unavailable",
+            LookupError::Other(_) => "The lookup failed",
+        }
+    }
+}
+
+impl From<&Error> for LookupError {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::DidntFindProduct => LookupError::NotFound,
+            Error::RateLimited => LookupError::RateLimited,
+            Error::Timeout => LookupError::Timeout,
+            Error::Network => LookupError::Offline,
+            Error::ServiceUnavailable(_) => LookupError::Unavailable,
+            err => LookupError::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<LookupError> for Error {
+    fn from(err: LookupError) -> Self {
+        match err {
+            LookupError::NotFound => Error::DidntFindProduct,
+            LookupError::RateLimited => Error::RateLimited,
+            LookupError::Timeout => Error::Timeout,
+            LookupError::Offline => Error::Network,
+            LookupError::Unavailable => Error::ServiceUnavailable(503),
+            LookupError::Other(msg) => Error::ServerFn(ServerFnErrorErr::ServerError(msg)),
+        }
+    }
+}
+
+impl From<ServerFnError<LookupError>> for Error {
+    fn from(err: ServerFnError<LookupError>) -> Self {
+        match err {
+            ServerFnError::WrappedServerError(err) => err.into(),
+            ServerFnError::Request(_) => Error::Network,
+            err => Error::ServerFn(ServerFnErrorErr::ServerError(err.to_string())),
+        }
+    }
+}
+
+/// The server functions (de)serialize custom errors with [`Display`] and
+/// [`FromStr`](std::str::FromStr).
+impl Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::NotFound => write!(f, "not_found"),
+            LookupError::RateLimited => write!(f, "rate_limited"),
+            LookupError::Timeout => write!(f, "timeout"),
+            LookupError::Offline => write!(f, "offline"),
+            LookupError::Unavailable => write!(f, "unavailable"),
+            LookupError::Other(msg) => write!(f, "other:{msg}"),
+        }
+    }
+}
+
+impl std::str::FromStr for LookupError {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "not_found" => LookupError::NotFound,
+            "rate_limited" => LookupError::RateLimited,
+            "timeout" => LookupError::Timeout,
+            "offline" => LookupError::Offline,
+            "unavailable" => LookupError::Unavailable,
+            s => LookupError::Other(s.strip_prefix("other:").unwrap_or(s).to_string()),
+        })
+    }
+}
+
+/// Requests the product from the [`crate::item::provider`]s through the
+/// server, which caches the responses. Set `refresh` to bypass the cache.
+#[server]
+pub async fn lookup_product(
+    barcode: Barcode,
+    refresh: bool,
+) -> std::result::Result<serde_json::Value, ServerFnError<LookupError>> {
+    crate::item::provider::request_with_barcode(barcode, refresh, &crate::db::MY_DB)
+        .await
+        .map_err(|err| ServerFnError::WrappedServerError(LookupError::from(&err)))
+}
+
+impl OpenFoodFactsProduct {
+    pub async fn request_with_barcode(barcode: Barcode, lang: Language) -> Result<Self> {
+        Self::lookup(barcode, false, lang).await
+    }
+
+    pub async fn lookup(barcode: Barcode, refresh: bool, lang: Language) -> Result<Self> {
+        let product = lookup_product(barcode, refresh).await?;
+        Self::from_json(product, lang)
+    }
+
+    /// Parses the `product` field of an [`OpenFoodFactsResponse`] and picks the
+    /// names in `lang` if possible. Falls back to the main language of the
+    /// product, then to any other [`Language`] and then to the generic name.
+    pub fn from_json(product: serde_json::Value, lang: Language) -> Result<Self> {
+        let generic_name = localized(&product, "generic_name", lang);
+        let (product_name, name_lang) = localized(&product, "product_name", lang)
+            .or_else(|| generic_name.clone())
+            .unwrap_or_default();
+        let generic_name = generic_name.map(|(name, _)| name).unwrap_or_default();
+        let product = serde_json::from_value::<Self>(product)?;
+        Ok(Self { product_name, name_lang, generic_name, ..product })
+    }
+}
+
+/// Returns the first non-empty value of `<field>_<lang>`, `<field>` or
+/// `<field>_<other lang>` together with its language code.
+fn localized(product: &serde_json::Value, field: &str, lang: Language) -> Option<(String, String)> {
+    let get = |key: &str| {
+        product
+            .get(key)
+            .and_then(serde_json::Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let in_lang =
+        |l: Language| get(&format!("{field}_{}", l.code())).map(|s| (s, l.code().to_string()));
+    let main_lang = product.get("lang").and_then(serde_json::Value::as_str).unwrap_or_default();
+
+    in_lang(lang)
+        .or_else(|| get(field).map(|s| (s, main_lang.to_string())))
+        .or_else(|| Language::ALL.into_iter().find_map(in_lang))
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// since the server started
+    pub hits: u64,
+    /// since the server started
+    pub misses: u64,
+    pub entries: u64,
+    pub not_found_entries: u64,
+}
+
+#[server]
+pub async fn get_cache_stats() -> std::result::Result<CacheStats, ServerFnError> {
+    Ok(cache::stats(&crate::db::MY_DB).await?)
+}
@@ -0,0 +1,50 @@
+use crate::{barcode_scanner::Gs1Data, util::json_column};
+use chrono::NaiveDate;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// The data of a single package from its GS1 DataMatrix or QR code, e.g. on
+/// fresh meat. It is kept for pantry and expiry features.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Package {
+    /// AI 15
+    pub best_before: Option<NaiveDate>,
+    /// AI 17, the "use by" date of perishable products.
+    pub expiry: Option<NaiveDate>,
+    /// AI 10, the lot number. Empty if unknown.
+    pub batch: String,
+}
+
+json_column!(Package);
+
+impl Package {
+    pub fn is_empty(&self) -> bool {
+        self == &Package::default()
+    }
+}
+
+impl From<&Gs1Data> for Package {
+    fn from(data: &Gs1Data) -> Self {
+        Package {
+            best_before: data.best_before,
+            expiry: data.expiry,
+            batch: data.batch.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[component]
+pub fn PackageView(package: Package) -> impl IntoView {
+    let date = |date: NaiveDate| date.format("%Y-%m-%d").to_string();
+    let text = [
+        package.expiry.map(|d| format!("use by {}", date(d))),
+        package.best_before.map(|d| format!("best before {}", date(d))),
+        (!package.batch.is_empty()).then(|| format!("lot {}", package.batch)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(", ");
+    (!text.is_empty()).then(|| view! { <span class="package sub-info">{ text }</span> })
+}
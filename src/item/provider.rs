@@ -0,0 +1,154 @@
+//! The sources of product data for barcode lookups. They are tried in the
+//! order of the `PRODUCT_PROVIDERS` environment variable, a comma-separated
+//! list of [`Provider`] names, until one of them knows the product.
+
+use super::openfoodsfacts::{cache, OpenFacts};
+use crate::{
+    barcode_scanner::Barcode,
+    db::DB,
+    error::{Error, Result},
+};
+use leptos::logging;
+use std::sync::RwLock;
+
+pub const DEFAULT_PROVIDERS: &str = "local,openfoodfacts,openbeautyfacts,openpetfoodfacts";
+
+lazy_static::lazy_static! {
+    static ref PROVIDERS: RwLock<Vec<Provider>> = RwLock::new(providers_from_env());
+}
+
+pub trait ProductProvider {
+    fn name(&self) -> &'static str;
+
+    /// Returns the product shaped like the `product` field of an
+    /// OpenFoodFacts response. `refresh` bypasses caches.
+    async fn request_with_barcode(
+        &self,
+        barcode: Barcode,
+        refresh: bool,
+        db: &DB,
+    ) -> Result<serde_json::Value>;
+}
+
+impl ProductProvider for OpenFacts {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn request_with_barcode(
+        &self,
+        barcode: Barcode,
+        refresh: bool,
+        db: &DB,
+    ) -> Result<serde_json::Value> {
+        cache::request_with_barcode(*self, barcode, refresh, db).await
+    }
+}
+
+/// The products in the `catalog` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalCatalog;
+
+impl ProductProvider for LocalCatalog {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn request_with_barcode(
+        &self,
+        barcode: Barcode,
+        _refresh: bool,
+        db: &DB,
+    ) -> Result<serde_json::Value> {
+        let mut conn = db.connection().await?;
+        let product = sqlx::query_scalar!("SELECT product FROM catalog WHERE barcode = ?", barcode)
+            .fetch_optional(conn.as_mut())
+            .await?
+            .ok_or(Error::DidntFindProduct)?;
+        Ok(serde_json::from_str(&product)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenFacts(OpenFacts),
+    LocalCatalog(LocalCatalog),
+}
+
+impl Provider {
+    pub fn from_name(name: &str) -> Option<Provider> {
+        match name {
+            "local" => Some(Provider::LocalCatalog(LocalCatalog)),
+            name => OpenFacts::ALL.into_iter().find(|o| o.name == name).map(Provider::OpenFacts),
+        }
+    }
+}
+
+impl ProductProvider for Provider {
+    fn name(&self) -> &'static str {
+        match self {
+            Provider::OpenFacts(p) => p.name(),
+            Provider::LocalCatalog(p) => p.name(),
+        }
+    }
+
+    async fn request_with_barcode(
+        &self,
+        barcode: Barcode,
+        refresh: bool,
+        db: &DB,
+    ) -> Result<serde_json::Value> {
+        match self {
+            Provider::OpenFacts(p) => p.request_with_barcode(barcode, refresh, db).await,
+            Provider::LocalCatalog(p) => p.request_with_barcode(barcode, refresh, db).await,
+        }
+    }
+}
+
+fn providers_from_env() -> Vec<Provider> {
+    let names =
+        std::env::var("PRODUCT_PROVIDERS").unwrap_or_else(|_| DEFAULT_PROVIDERS.to_string());
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| {
+            let provider = Provider::from_name(name);
+            if provider.is_none() {
+                logging::warn!("unknown product provider: {:?}", name);
+            }
+            provider
+        })
+        .collect()
+}
+
+pub fn providers() -> Vec<Provider> {
+    PROVIDERS.read().unwrap().clone()
+}
+
+/// Overrides the `PRODUCT_PROVIDERS` environment variable.
+pub fn set_providers(providers: Vec<Provider>) {
+    *PROVIDERS.write().unwrap() = providers;
+}
+
+/// Asks the [`providers`] in order. If none of them knows the product, the
+/// last other error, e.g. [`Error::RateLimited`], is returned, so the user
+/// knows that a retry might help.
+pub async fn request_with_barcode(
+    barcode: Barcode,
+    refresh: bool,
+    db: &DB,
+) -> Result<serde_json::Value> {
+    let mut result = Err(Error::DidntFindProduct);
+    for provider in providers() {
+        match provider.request_with_barcode(barcode, refresh, db).await {
+            Ok(product) => return Ok(product),
+            Err(Error::DidntFindProduct) => (),
+            Err(err) => {
+                logging::warn!("product provider {} failed: {}", provider.name(), err);
+                result = Err(err);
+            },
+        }
+    }
+    result
+}
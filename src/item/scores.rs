@@ -0,0 +1,149 @@
+use super::openfoodsfacts::{NutrientLevels, OpenFoodFactsProduct};
+use crate::util::json_column;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NutriScore {
+    A,
+    B,
+    C,
+    D,
+    E,
+}
+
+impl NutriScore {
+    pub const ALL: [NutriScore; 5] =
+        [NutriScore::A, NutriScore::B, NutriScore::C, NutriScore::D, NutriScore::E];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NutriScore::A => "A",
+            NutriScore::B => "B",
+            NutriScore::C => "C",
+            NutriScore::D => "D",
+            NutriScore::E => "E",
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for NutriScore {
+    type Error = &'a str;
+
+    fn try_from(grade: &'a str) -> Result<Self, Self::Error> {
+        match grade {
+            "a" | "A" => Ok(NutriScore::A),
+            "b" | "B" => Ok(NutriScore::B),
+            "c" | "C" => Ok(NutriScore::C),
+            "d" | "D" => Ok(NutriScore::D),
+            "e" | "E" => Ok(NutriScore::E),
+            grade => Err(grade),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Level {
+    Low,
+    Moderate,
+    High,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Low => "low",
+            Level::Moderate => "moderate",
+            Level::High => "high",
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Level {
+    type Error = &'a str;
+
+    fn try_from(level: &'a str) -> Result<Self, Self::Error> {
+        match level {
+            "low" => Ok(Level::Low),
+            "moderate" => Ok(Level::Moderate),
+            "high" => Ok(Level::High),
+            level => Err(level),
+        }
+    }
+}
+
+/// Nutri-Score, NOVA group and the traffic-light levels of a product. Missing
+/// values are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scores {
+    pub nutriscore: Option<NutriScore>,
+    /// 1 (unprocessed) to 4 (ultra-processed)
+    pub nova_group: Option<u8>,
+    pub fat: Option<Level>,
+    pub saturated_fat: Option<Level>,
+    pub sugars: Option<Level>,
+    pub salt: Option<Level>,
+}
+
+json_column!(Scores);
+
+impl From<&OpenFoodFactsProduct> for Scores {
+    fn from(product: &OpenFoodFactsProduct) -> Self {
+        let NutrientLevels { fat, salt, saturated_fat, sugars } = &product.nutrient_levels;
+        let level = |l: &String| Level::try_from(l.as_str()).ok();
+        Scores {
+            nutriscore: NutriScore::try_from(product.nutriscore_grade.as_str()).ok(),
+            nova_group: product.nova_group.filter(|g| (1..=4).contains(g)),
+            fat: level(fat),
+            saturated_fat: level(saturated_fat),
+            sugars: level(sugars),
+            salt: level(salt),
+        }
+    }
+}
+
+impl Scores {
+    fn levels(&self) -> [(&'static str, Option<Level>); 4] {
+        [
+            ("Fat", self.fat),
+            ("Saturated fat", self.saturated_fat),
+            ("Sugars", self.sugars),
+            ("Salt", self.salt),
+        ]
+    }
+}
+
+#[component]
+pub fn ScoreBadges(scores: Scores) -> impl IntoView {
+    let nutriscore = scores.nutriscore.map(|grade| {
+        view! {
+            <span class="nutriscore" grade=grade.as_str() title="Nutri-Score">
+                { grade.as_str() }
+            </span>
+        }
+    });
+    let nova = scores.nova_group.map(|group| {
+        view! {
+            <span class="nova" group=group title="NOVA group">{ group }</span>
+        }
+    });
+    let levels = scores
+        .levels()
+        .into_iter()
+        .filter_map(|(name, level)| level.map(|l| (name, l)))
+        .map(|(name, level)| {
+            let title = format!("{name}: {}", level.as_str());
+            view! { <span class="level" level=level.as_str() title=title></span> }
+        })
+        .collect_view();
+
+    view! {
+        <div class="score-badges">
+            { nutriscore }
+            { nova }
+            { levels }
+        </div>
+    }
+}
@@ -1,9 +1,11 @@
-use super::{data::Item, variant_data::NewVariant};
+use super::{amount::Amount, data::Item, variant_data::NewVariant};
 use crate::{
     barcode_scanner::Barcode,
     item::data::{ItemImpl, NewItem},
+    language::Language,
     list::List,
 };
+use chrono::NaiveDate;
 use leptos::{logging, server, Action, MultiAction, ServerFnError};
 use serde::{Deserialize, Serialize};
 
@@ -17,9 +19,13 @@ pub async fn get_list() -> Result<List, ServerFnError> {
 
 /// Returns id of the created item.
 #[server]
-pub async fn add_item_from_barcode(barcode: Barcode) -> Result<i64, ServerFnError> {
-    let i = NewItem::from_barcode(barcode).await?;
+pub async fn add_item_from_barcode(
+    barcode: Barcode,
+    lang: Language,
+) -> Result<i64, ServerFnError> {
+    let i = NewItem::from_barcode(barcode, lang).await?;
     let i = i.insert(&crate::db::MY_DB).await?;
+    crate::image_store::wake();
     Ok(i.id)
 }
 
@@ -39,11 +45,34 @@ pub async fn set_completed(item_id: i64, completed: bool) -> Result<(), ServerFn
 }
 
 #[server]
-pub async fn set_amount(item_id: i64, amount: u64) -> Result<(), ServerFnError> {
+pub async fn set_amount(item_id: i64, amount: Amount) -> Result<(), ServerFnError> {
     logging::log!("set amount for {item_id}: {amount}");
-    let amount = amount as i64;
+    let (value, unit) = (amount.value(), amount.unit.as_str());
+    let mut conn = crate::db::MY_DB.connection().await?;
+    sqlx::query!("UPDATE item SET amount = ?, unit = ? WHERE id = ?", value, unit, item_id)
+        .execute(conn.as_mut())
+        .await?;
+    Ok(())
+}
+
+#[server]
+pub async fn set_priority(item_id: i64, priority: bool) -> Result<(), ServerFnError> {
+    logging::log!("set priority for {item_id}: {priority}");
+    let mut conn = crate::db::MY_DB.connection().await?;
+    sqlx::query!("UPDATE item SET priority = ? WHERE id = ?", priority, item_id)
+        .execute(conn.as_mut())
+        .await?;
+    Ok(())
+}
+
+#[server]
+pub async fn set_needed_by(
+    item_id: i64,
+    needed_by: Option<NaiveDate>,
+) -> Result<(), ServerFnError> {
+    logging::log!("set needed_by for {item_id}: {needed_by:?}");
     let mut conn = crate::db::MY_DB.connection().await?;
-    sqlx::query!("UPDATE item SET amount = ? WHERE id = ?", amount, item_id)
+    sqlx::query!("UPDATE item SET needed_by = ? WHERE id = ?", needed_by, item_id)
         .execute(conn.as_mut())
         .await?;
     Ok(())
@@ -58,6 +87,7 @@ pub struct ItemIds {
 #[server]
 pub async fn insert_from_client(new_item: NewItem) -> Result<ItemIds, ServerFnError> {
     let item = new_item.insert(&crate::db::MY_DB).await?;
+    crate::image_store::wake();
     // std::thread::sleep(std::time::Duration::from_millis(10000));
     Ok(ItemIds { item_id: item.id, variant_ids: item.variants.into_iter().map(|a| a.id).collect() })
 }
@@ -75,5 +105,6 @@ pub async fn insert_variant_from_client(
 ) -> Result<i64, ServerFnError> {
     let mut conn = crate::db::MY_DB.connection().await?;
     let variant = new_variant.insert(item_id, conn.as_mut()).await?;
+    crate::image_store::wake();
     Ok(variant.id)
 }
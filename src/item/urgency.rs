@@ -0,0 +1,71 @@
+use super::data::Urgency;
+use crate::popup::{Popup, PopupSignal};
+use chrono::NaiveDate;
+use leptos::*;
+
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+#[component]
+pub fn ItemUrgency<P, N>(priority: P, needed_by: N, urgency: Signal<Urgency>) -> impl IntoView
+where
+    P: SignalSet<Value = bool> + SignalGet<Value = bool> + Copy + 'static,
+    N: SignalSet<Value = Option<NaiveDate>> + SignalGet<Value = Option<NaiveDate>> + Copy + 'static,
+{
+    let popup = PopupSignal::new();
+
+    let title = move || match (priority.get(), needed_by.get()) {
+        (_, Some(date)) => format!("needed by {}", date.format("%a, %Y-%m-%d")),
+        (true, None) => "High priority".to_string(),
+        (false, None) => "Set priority".to_string(),
+    };
+    let is_set = move || priority.get() || needed_by.get().is_some();
+    let date_value =
+        move || needed_by.get().map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+
+    view! {
+        <img
+            src="img/flag.svg"
+            alt=title
+            title=title
+            class="urgency-button cursor-pointer"
+            class:set=is_set
+            class:urgent=move || urgency() == Urgency::Urgent
+            class:overdue=move || urgency() == Urgency::Overdue
+            on:click=move |ev| {
+                ev.stop_propagation();
+                popup.open();
+            }
+        />
+        <Popup popup>
+            <div class="item-urgency" on:click=|ev| ev.stop_propagation()>
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || priority.get()
+                        on:input=move |ev| priority.set(event_target_checked(&ev))
+                    />
+                    "High priority"
+                </label>
+                <label>
+                    "Needed by "
+                    <input
+                        type="date"
+                        prop:value=date_value
+                        on:change=move |ev| needed_by.set(event_target_value(&ev).parse().ok())
+                    />
+                </label>
+                <button
+                    class="cursor-pointer"
+                    on:click=move |_| {
+                        priority.set(false);
+                        needed_by.set(None);
+                    }
+                >
+                    "Clear"
+                </button>
+            </div>
+        </Popup>
+    }
+}
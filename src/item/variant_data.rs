@@ -1,7 +1,11 @@
-use super::openfoodsfacts::OpenFoodFactsProduct;
+use super::{
+    dietary::Dietary, nutrition::Nutrition, openfoodsfacts::OpenFoodFactsProduct,
+    package::Package, scores::Scores,
+};
 use crate::{
     barcode_scanner::{Barcode, OptionBarcode},
     error::Result,
+    language::Language,
 };
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +14,9 @@ use serde::{Deserialize, Serialize};
 pub struct VariantImpl<ID> {
     pub id: ID,
     pub name: String,
+    /// The language code of `name`. Empty if unknown.
+    #[serde(default)]
+    pub name_lang: String,
 
     //pub shop_id: Option<i64>,
     pub shop: String,
@@ -19,11 +26,23 @@ pub struct VariantImpl<ID> {
     pub thumb_url: Option<String>,
     pub packaging: String,
     pub quantity: String,
+    #[serde(default)]
+    pub nutrition: Nutrition,
+    #[serde(default)]
+    pub scores: Scores,
+    #[serde(default)]
+    pub dietary: Dietary,
+    #[serde(default)]
+    pub package: Package,
 }
 
 pub type Variant = VariantImpl<i64>;
 
 impl Variant {
+    pub fn without_id(self) -> NewVariant {
+        NewVariant { id: (), ..self }
+    }
+
     #[cfg(feature = "ssr")]
     pub async fn for_item(
         item_id: i64,
@@ -31,8 +50,12 @@ impl Variant {
     ) -> Result<Vec<Self>> {
         Ok(sqlx::query_as!(
             Variant,
-            "SELECT id, name, shop, barcode, brands, img_url, thumb_url, packaging, quantity FROM \
-             item_variant WHERE variant_of = ?",
+            r#"SELECT id, name, name_lang, shop, barcode as "barcode: Barcode", brands, img_url,
+            thumb_url, packaging, quantity, nutrition as "nutrition: Nutrition",
+            scores as "scores: Scores",
+            dietary as "dietary: Dietary",
+            package as "package: Package"
+            FROM item_variant WHERE variant_of = ?"#,
             item_id
         )
         .fetch_all(conn)
@@ -47,6 +70,7 @@ impl Default for NewVariant {
         Self {
             id: (),
             name: "".to_string(),
+            name_lang: "".to_string(),
             //shop_id: None,
             shop: "".to_string(),
             barcode: OptionBarcode::none(),
@@ -55,20 +79,28 @@ impl Default for NewVariant {
             thumb_url: None,
             packaging: "".to_string(),
             quantity: "".to_string(),
+            nutrition: Nutrition::default(),
+            scores: Scores::default(),
+            dietary: Dietary::default(),
+            package: Package::default(),
         }
     }
 }
 
 impl NewVariant {
-    pub async fn from_barcode(barcode: Barcode) -> Result<Self> {
-        OpenFoodFactsProduct::request_with_barcode(barcode).await.map(|data| Self {
+    pub async fn from_barcode(barcode: Barcode, lang: Language) -> Result<Self> {
+        OpenFoodFactsProduct::request_with_barcode(barcode, lang).await.map(|data| Self {
+            scores: Scores::from(&data),
+            dietary: Dietary::from(&data),
             name: data.product_name,
+            name_lang: data.name_lang,
             barcode: OptionBarcode::some(barcode),
             img_url: Some(data.image_url),
             thumb_url: Some(data.image_thumb_url),
             brands: data.brands,
             packaging: data.packaging,
             quantity: data.quantity,
+            nutrition: Nutrition::from(&data.nutriments),
             ..Self::default()
         })
     }
@@ -84,17 +116,22 @@ impl NewVariant {
         conn: impl sqlx::Executor<'_, Database = crate::db::DBType>,
     ) -> Result<Variant> {
         let id = sqlx::query!(
-            r#"INSERT INTO item_variant(variant_of, name, shop, barcode, brands, img_url, thumb_url, packaging, quantity)
-            VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ? )"#,
+            r#"INSERT INTO item_variant(variant_of, name, name_lang, shop, barcode, brands, img_url, thumb_url, packaging, quantity, nutrition, scores, dietary, package)
+            VALUES ( ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ? )"#,
             item_id,
             self.name,
+            self.name_lang,
             self.shop,
             self.barcode,
             self.brands,
             self.img_url,
             self.thumb_url,
             self.packaging,
-            self.quantity
+            self.quantity,
+            self.nutrition,
+            self.scores,
+            self.dietary,
+            self.package
         )
         .execute(conn)
         .await?
@@ -1,17 +1,39 @@
+use crate::app::use_window;
 use leptos::logging;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Language {
     #[default]
     English,
+    German,
 }
 
 impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
     pub fn new(lang: impl AsRef<str>) -> Option<Language> {
         Self::try_from(lang.as_ref())
             .inspect_err(|e| logging::warn!("unknown language: {:?}", e))
             .ok()
     }
+
+    /// The preferred language of the browser. Always the default on the
+    /// server.
+    pub fn from_navigator() -> Language {
+        use_window()
+            .and_then(|w| w.navigator().language())
+            .and_then(Language::new)
+            .unwrap_or_default()
+    }
+
+    /// ISO 639-1 code, as used by OpenFoodFacts.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
 }
 
 impl<'a> TryFrom<&'a str> for Language {
@@ -22,6 +44,8 @@ impl<'a> TryFrom<&'a str> for Language {
             "en" => Ok(Language::English),
             l if l.starts_with("en-") => Ok(Language::English),
             // "en-US" => Ok(Language::English), // TODO
+            "de" => Ok(Language::German),
+            l if l.starts_with("de-") => Ok(Language::German),
             lang => Err(lang),
         }
     }
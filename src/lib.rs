@@ -9,7 +9,7 @@ mod auth;
 mod barcode_scanner;
 mod camera;
 #[cfg(feature = "ssr")]
-mod db;
+pub mod db;
 mod db_tool;
 mod default_resource;
 mod error;
@@ -17,6 +17,7 @@ mod error;
 pub mod fileserv;
 mod header_bar;
 mod image;
+pub mod image_store;
 mod item;
 mod language;
 mod list;
@@ -24,10 +25,18 @@ mod login;
 mod main_page;
 mod option_signal;
 mod popup;
+mod profile;
+mod recipe;
+pub mod recurrence;
+mod scan;
 mod server_sync_signal;
 mod subsignal;
+mod template;
 mod util;
 
+#[cfg(feature = "ssr")]
+pub use item::{catalog, openfoodsfacts::contribute};
+
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn hydrate() {
@@ -1,12 +1,16 @@
 use crate::{
     barcode_scanner::Barcode,
     item::{
-        data::{Item, NewItem, PendingItem},
+        amount::{Amount, Unit},
+        data::{Item, NewItem, PendingItem, Urgency},
+        scores::NutriScore,
         server_functions::{get_list, InsertFromClient, InsertFromClientAction, ItemIds},
+        urgency::today,
         ItemView, NewItemView, RefreshList, ShowNewItem,
     },
     util::{force_use_context, VecExt},
 };
+use chrono::NaiveDate;
 use leptos::*;
 use serde::{Deserialize, Serialize};
 use std::vec;
@@ -17,7 +21,6 @@ pub struct List(pub(crate) Vec<Item>);
 impl IntoView for List {
     fn into_view(self) -> View {
         self.0
-            .sorted_by(|a, b| a.amount.cmp(&b.amount).reverse())
             .into_iter()
             .map(|item| {
                 view! { <ItemView item/> }
@@ -40,6 +43,60 @@ impl List {
         let Some(idx) = self.0.iter().position(|i| i.id == id) else { return };
         self.0.remove(idx);
     }
+
+    pub fn sorted(self, order: ListOrder) -> List {
+        let today = today();
+        List(match order {
+            ListOrder::Amount => self.0.sorted_by(|a, b| cmp_amount(a, b).reverse()),
+            ListOrder::Urgency => self.0.sorted_by(|a, b| {
+                let needed_by = |i: &Item| i.needed_by.unwrap_or(NaiveDate::MAX);
+                a.urgency(today)
+                    .cmp(&b.urgency(today))
+                    .reverse()
+                    .then_with(|| needed_by(a).cmp(&needed_by(b)))
+                    .then_with(|| cmp_amount(a, b).reverse())
+            }),
+        })
+    }
+
+    pub fn filtered(self, filter: ListFilter) -> List {
+        let today = today();
+        List(self.0.into_iter().filter(|item| filter.matches(item, today)).collect())
+    }
+}
+
+/// Weights and volumes are compared in grams and millilitres, so 2 kg sorts
+/// above 500 g. Other units only compare their values, mostly to move items
+/// with a zero amount to the end.
+fn cmp_amount(a: &Item, b: &Item) -> std::cmp::Ordering {
+    let base_value = |amount: Amount| {
+        let base = amount.convert_to(Unit::Gram).or_else(|| amount.convert_to(Unit::Millilitre));
+        base.unwrap_or(amount).value()
+    };
+    base_value(a.amount).total_cmp(&base_value(b.amount))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListOrder {
+    #[default]
+    Amount,
+    Urgency,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    pub only_urgent: bool,
+    /// Only show items with a variant which has this Nutri-Score or a better
+    /// one.
+    pub min_nutriscore: Option<NutriScore>,
+}
+
+impl ListFilter {
+    pub fn matches(&self, item: &Item, today: NaiveDate) -> bool {
+        let best_nutriscore = || item.variants.iter().filter_map(|v| v.scores.nutriscore).min();
+        (!self.only_urgent || item.urgency(today) > Urgency::Normal)
+            && self.min_nutriscore.is_none_or(|min| best_nutriscore().is_some_and(|g| g <= min))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -73,6 +130,10 @@ pub fn ListView() -> impl IntoView {
         items.0.refetch();
     });
 
+    let order = create_rw_signal(ListOrder::default());
+    let filter = create_rw_signal(ListFilter::default());
+    let list_view = move |list: List| list.filtered(filter()).sorted(order()).into_view();
+
     let submissions = insert_from_client.submissions();
     let pending_items = move || {
         submissions.with(|vec| {
@@ -85,9 +146,41 @@ pub fn ListView() -> impl IntoView {
 
     view! {
         <ul id="shopping_list">
+            <li class="list-controls">
+                <select on:change=move |ev| order.set(match event_target_value(&ev).as_str() {
+                    "urgency" => ListOrder::Urgency,
+                    _ => ListOrder::Amount,
+                })>
+                    <option value="amount" selected>"Sort by amount"</option>
+                    <option value="urgency">"Sort by urgency"</option>
+                </select>
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || filter().only_urgent
+                        on:input=move |ev| filter.update(|f| f.only_urgent = event_target_checked(&ev))
+                    />
+                    "Only urgent"
+                </label>
+                <select on:change=move |ev| filter.update(|f| {
+                    f.min_nutriscore = NutriScore::try_from(event_target_value(&ev).as_str()).ok()
+                })>
+                    <option value="" selected>"Any Nutri-Score"</option>
+                    {
+                        NutriScore::ALL
+                            .into_iter()
+                            .map(|grade| view! {
+                                <option value=grade.as_str()>
+                                    { format!("Nutri-Score {} or better", grade.as_str()) }
+                                </option>
+                            })
+                            .collect_view()
+                    }
+                </select>
+            </li>
             <NewItemView show=show_new_item />
             <Transition fallback=move || view! { <p>"Loading..."</p> }>
-                { move || items.0().map(List::into_view) }
+                { move || items.0().map(list_view) }
             </Transition>
             { pending_items }
         </ul>
@@ -4,7 +4,10 @@ async fn main() {
     use axum::Router;
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
-    use shopping_list::{app::*, fileserv::file_and_error_handler};
+    use axum::routing::get;
+    use shopping_list::{
+        app::*, contribute, fileserv::file_and_error_handler, image_store, recurrence,
+    };
     use tower_http::compression::CompressionLayer;
 
     // Setting get_configuration(None) means we'll be using cargo-leptos's env
@@ -20,11 +23,16 @@ async fn main() {
 
     // build our application with a route
     let app = Router::new()
+        .route(&format!("{}/:file", image_store::ROUTE), get(image_store::serve_image))
         .layer(CompressionLayer::new())
         .leptos_routes(&leptos_options, routes, App)
         .fallback(file_and_error_handler)
         .with_state(leptos_options);
 
+    tokio::spawn(recurrence::run_scheduler());
+    tokio::spawn(contribute::run_contribution_worker());
+    tokio::spawn(image_store::run_image_worker());
+
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     logging::log!("listening on http://{}", &addr);
     axum::serve(listener, app.into_make_service()).await.unwrap();
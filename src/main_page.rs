@@ -2,6 +2,7 @@ use crate::{
     header_bar::HeaderBar,
     item::{RefreshList, ShowNewItem},
     list::ListView,
+    profile::ActiveProfiles,
 };
 use leptos::*;
 
@@ -9,6 +10,7 @@ use leptos::*;
 pub fn MainPage() -> impl IntoView {
     provide_context(ShowNewItem::default());
     provide_context(RefreshList::default());
+    provide_context(ActiveProfiles::new());
 
     view! {
         <HeaderBar />
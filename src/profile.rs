@@ -0,0 +1,427 @@
+use crate::item::dietary::Dietary;
+#[cfg(feature = "ssr")]
+use crate::{db::DB, error::Error};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The allergens which have to be labeled in the EU as
+/// `(OpenFoodFacts tag, label)`.
+pub const ALLERGENS: &[(&str, &str)] = &[
+    ("en:gluten", "Gluten"),
+    ("en:crustaceans", "Crustaceans"),
+    ("en:eggs", "Eggs"),
+    ("en:fish", "Fish"),
+    ("en:peanuts", "Peanuts"),
+    ("en:soybeans", "Soy"),
+    ("en:milk", "Milk"),
+    ("en:nuts", "Nuts"),
+    ("en:celery", "Celery"),
+    ("en:mustard", "Mustard"),
+    ("en:sesame-seeds", "Sesame"),
+    ("en:sulphur-dioxide-and-sulphites", "Sulphites"),
+    ("en:lupin", "Lupin"),
+    ("en:molluscs", "Molluscs"),
+];
+
+/// The label of an allergen tag, e.g. "Nuts" for "en:nuts".
+pub fn allergen_label(tag: &str) -> &str {
+    ALLERGENS
+        .iter()
+        .find(|(t, _)| *t == tag)
+        .map(|(_, label)| *label)
+        .unwrap_or_else(|| tag.split_once(':').map_or(tag, |(_, name)| name))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Diet {
+    Vegetarian,
+    Vegan,
+    PalmOilFree,
+}
+
+impl Diet {
+    pub const ALL: [Diet; 3] = [Diet::Vegetarian, Diet::Vegan, Diet::PalmOilFree];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Diet::Vegetarian => "vegetarian",
+            Diet::Vegan => "vegan",
+            Diet::PalmOilFree => "palm oil free",
+        }
+    }
+
+    /// The `ingredients_analysis_tags` of OpenFoodFacts which violate this
+    /// diet as `(certain, maybe)`.
+    fn analysis_tags(self) -> (&'static str, &'static str) {
+        match self {
+            Diet::Vegetarian => ("en:non-vegetarian", "en:maybe-vegetarian"),
+            Diet::Vegan => ("en:non-vegan", "en:maybe-vegan"),
+            Diet::PalmOilFree => ("en:palm-oil", "en:may-contain-palm-oil"),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Diet {
+    type Error = &'a str;
+
+    fn try_from(diet: &'a str) -> Result<Self, Self::Error> {
+        match diet {
+            "vegetarian" => Ok(Diet::Vegetarian),
+            "vegan" => Ok(Diet::Vegan),
+            "palm oil free" => Ok(Diet::PalmOilFree),
+            diet => Err(diet),
+        }
+    }
+}
+
+/// A member of the household with their allergies and diets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    /// OpenFoodFacts allergen tags, e.g. "en:nuts".
+    pub allergens: Vec<String>,
+    pub diets: Vec<Diet>,
+    /// Only active profiles produce warnings.
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The product may contain traces or the analysis isn't sure.
+    Maybe,
+    Certain,
+}
+
+/// Why a product isn't suitable for a [`Profile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub profile: String,
+    pub reason: String,
+    pub severity: Severity,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.profile, self.reason)
+    }
+}
+
+impl Profile {
+    pub fn conflicts_with(&self, dietary: &Dietary) -> Vec<Conflict> {
+        let conflict = |reason: String, severity| Conflict {
+            profile: self.name.clone(),
+            reason,
+            severity,
+        };
+        let allergens = self.allergens.iter().filter_map(|a| {
+            if dietary.allergens.contains(a) {
+                Some(conflict(format!("contains {}", allergen_label(a)), Severity::Certain))
+            } else if dietary.traces.contains(a) {
+                Some(conflict(format!("may contain {}", allergen_label(a)), Severity::Maybe))
+            } else {
+                None
+            }
+        });
+        let diets = self.diets.iter().filter_map(|diet| {
+            let (certain, maybe) = diet.analysis_tags();
+            let has_tag = |tag: &str| dietary.analysis.iter().any(|t| t == tag);
+            if has_tag(certain) {
+                Some(conflict(format!("not {}", diet.as_str()), Severity::Certain))
+            } else if has_tag(maybe) {
+                Some(conflict(format!("maybe not {}", diet.as_str()), Severity::Maybe))
+            } else {
+                None
+            }
+        });
+        allergens.chain(diets).collect()
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Profile {
+    pub async fn select_all(db: &DB) -> Result<Vec<Self>, Error> {
+        let mut conn = db.connection().await?;
+        sqlx::query!("SELECT id, name, allergens, diets, active FROM profile ORDER BY name")
+            .fetch_all(conn.as_mut())
+            .await?
+            .into_iter()
+            .map(|r| {
+                let allergens = serde_json::from_str(&r.allergens)?;
+                let diets = serde_json::from_str(&r.diets)?;
+                Ok(Profile { id: r.id, name: r.name, allergens, diets, active: r.active })
+            })
+            .collect()
+    }
+
+    /// Returns the id of the new profile.
+    pub async fn insert(
+        name: &str,
+        allergens: &[String],
+        diets: &[Diet],
+        db: &DB,
+    ) -> Result<i64, Error> {
+        let allergens = serde_json::to_string(allergens)?;
+        let diets = serde_json::to_string(diets)?;
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!(
+            "INSERT INTO profile(name, allergens, diets) VALUES ( ?, ?, ? )",
+            name,
+            allergens,
+            diets
+        )
+        .execute(conn.as_mut())
+        .await?
+        .last_insert_rowid())
+    }
+
+    pub async fn set_active(id: i64, active: bool, db: &DB) -> Result<(), Error> {
+        let mut conn = db.connection().await?;
+        sqlx::query!("UPDATE profile SET active = ? WHERE id = ?", active, id)
+            .execute(conn.as_mut())
+            .await?;
+        Ok(())
+    }
+
+    /// Returns whether rows where affected or not
+    pub async fn remove(id: i64, db: &DB) -> Result<bool, Error> {
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!("DELETE FROM profile WHERE id = ?", id)
+            .execute(conn.as_mut())
+            .await?
+            .rows_affected()
+            > 0)
+    }
+}
+
+#[server]
+pub async fn get_profiles() -> Result<Vec<Profile>, ServerFnError> {
+    Ok(Profile::select_all(&crate::db::MY_DB).await?)
+}
+
+/// Returns the id of the new profile.
+#[server]
+pub async fn add_profile(
+    name: String,
+    allergens: Vec<String>,
+    diets: Vec<Diet>,
+) -> Result<i64, ServerFnError> {
+    Ok(Profile::insert(name.trim(), &allergens, &diets, &crate::db::MY_DB).await?)
+}
+
+#[server]
+pub async fn set_profile_active(id: i64, active: bool) -> Result<(), ServerFnError> {
+    Ok(Profile::set_active(id, active, &crate::db::MY_DB).await?)
+}
+
+#[server]
+pub async fn remove_profile(id: i64) -> Result<bool, ServerFnError> {
+    Profile::remove(id, &crate::db::MY_DB).await.map_err(Into::into)
+}
+
+/// The active profiles, used for the warnings on the variants.
+#[derive(Debug, Clone, Copy)]
+pub struct ActiveProfiles(pub Resource<(), Vec<Profile>>);
+
+impl ActiveProfiles {
+    pub fn new() -> ActiveProfiles {
+        ActiveProfiles(create_resource(
+            || (),
+            |_| async {
+                get_profiles()
+                    .await
+                    .inspect_err(|err| logging::error!("ERROR while getting profiles: {}", err))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|p| p.active)
+                    .collect()
+            },
+        ))
+    }
+
+    /// All conflicts of the active profiles with `dietary`.
+    pub fn conflicts(&self, dietary: &Dietary) -> Vec<Conflict> {
+        self.0.with(|profiles| {
+            profiles.iter().flatten().flat_map(|p| p.conflicts_with(dietary)).collect()
+        })
+    }
+}
+
+#[component]
+pub fn ProfilesPage() -> impl IntoView {
+    let add_profile = create_server_action::<AddProfile>();
+    let set_profile_active = create_server_action::<SetProfileActive>();
+    let remove_profile = create_server_action::<RemoveProfile>();
+
+    let profiles = create_resource(
+        move || (add_profile.version().get(), remove_profile.version().get()),
+        |_| async {
+            get_profiles()
+                .await
+                .inspect_err(|err| logging::error!("ERROR while getting profiles: {}", err))
+                .unwrap_or_default()
+        },
+    );
+
+    let profile_view = move |Profile { id, name, allergens, diets, active }: Profile| {
+        let remove = move |_| match window().confirm_with_message("Remove Profile?") {
+            Ok(ok) if ok => remove_profile.dispatch(RemoveProfile { id }),
+            _ => (),
+        };
+        let restrictions = allergens
+            .iter()
+            .map(|a| allergen_label(a))
+            .chain(diets.iter().map(|d| d.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        view! {
+            <li class="profile">
+                <span class="name">{ name }</span>
+                <span class="restrictions sub-info">{ restrictions }</span>
+                <label>
+                    <input
+                        type="checkbox"
+                        class="cursor-pointer"
+                        prop:checked=active
+                        on:change=move |ev| set_profile_active
+                            .dispatch(SetProfileActive { id, active: event_target_checked(&ev) })
+                    />
+                    "active"
+                </label>
+                <button class="cursor-pointer" on:click=remove>"Remove"</button>
+            </li>
+        }
+    };
+
+    view! {
+        <section id="profiles">
+            <A href="/">"Back to the list"</A>
+            <h1>"Profiles"</h1>
+            <ul class="profile-list">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    {
+                        move || profiles().map(|profiles| {
+                            profiles.into_iter().map(profile_view).collect_view()
+                        })
+                    }
+                </Transition>
+            </ul>
+            <NewProfileView add_profile />
+        </section>
+    }
+}
+
+#[component]
+pub fn NewProfileView(
+    add_profile: Action<AddProfile, Result<i64, ServerFnError>>,
+) -> impl IntoView {
+    let name = create_rw_signal(String::new());
+    let allergens = create_rw_signal(Vec::<String>::new());
+    let diets = create_rw_signal(Vec::<Diet>::new());
+
+    let save = move |_| {
+        if name.with_untracked(|n| n.trim().is_empty()) {
+            window().alert_with_message("empty Name not allowed").unwrap();
+            return;
+        }
+        add_profile.dispatch(AddProfile {
+            name: name.get_untracked(),
+            allergens: allergens.get_untracked(),
+            diets: diets.get_untracked(),
+        });
+        name.set(String::new());
+        allergens.set(vec![]);
+        diets.set(vec![]);
+    };
+
+    fn toggle<T: PartialEq + Clone>(list: RwSignal<Vec<T>>, value: T, checked: bool) {
+        list.update(|l| {
+            l.retain(|v| *v != value);
+            if checked {
+                l.push(value);
+            }
+        })
+    }
+
+    let allergen_checkbox = move |(tag, label): &'static (&'static str, &'static str)| {
+        view! {
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || allergens.with(|a| a.iter().any(|t| t == tag))
+                    on:change=move |ev| toggle(allergens, tag.to_string(), event_target_checked(&ev))
+                />
+                { *label }
+            </label>
+        }
+    };
+    let diet_checkbox = move |diet: Diet| {
+        view! {
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || diets.with(|d| d.contains(&diet))
+                    on:change=move |ev| toggle(diets, diet, event_target_checked(&ev))
+                />
+                { diet.as_str() }
+            </label>
+        }
+    };
+
+    view! {
+        <div class="profile new">
+            <h3>"New Profile"</h3>
+            <input
+                type="text"
+                class="name"
+                placeholder="Name"
+                prop:value=name
+                on:change=move |ev| name.set(event_target_value(&ev))
+            />
+            <fieldset class="allergens">
+                <legend>"Allergens"</legend>
+                { ALLERGENS.iter().map(allergen_checkbox).collect_view() }
+            </fieldset>
+            <fieldset class="diets">
+                <legend>"Diets"</legend>
+                { Diet::ALL.into_iter().map(diet_checkbox).collect_view() }
+            </fieldset>
+            <button class="cursor-pointer" on:click=save>"Save"</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicts() {
+        let profile = Profile {
+            id: 1,
+            name: "Alex".to_string(),
+            allergens: vec!["en:nuts".to_string(), "en:milk".to_string()],
+            diets: vec![Diet::Vegetarian, Diet::Vegan],
+            active: true,
+        };
+        let dietary = Dietary {
+            allergens: vec!["en:milk".to_string()],
+            traces: vec!["en:nuts".to_string()],
+            analysis: vec!["en:maybe-vegetarian".to_string(), "en:non-vegan".to_string()],
+        };
+        let conflicts = profile
+            .conflicts_with(&dietary)
+            .into_iter()
+            .map(|c| (c.reason, c.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(conflicts, [
+            ("may contain Nuts".to_string(), Severity::Maybe),
+            ("contains Milk".to_string(), Severity::Certain),
+            ("maybe not vegetarian".to_string(), Severity::Maybe),
+            ("not vegan".to_string(), Severity::Certain),
+        ]);
+        assert!(profile.conflicts_with(&Dietary::default()).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    barcode_scanner::{Barcode, OptionBarcode},
+    error::{Error, Result},
+    item::{
+        amount::{Amount, Unit},
+        data::NewItem,
+        variant_data::NewVariant,
+    },
+    language::Language,
+};
+#[cfg(feature = "ssr")]
+use crate::db::{DBType, DB};
+use leptos::logging;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeImpl<ID> {
+    pub id: ID,
+    pub name: String,
+    /// The number of servings the ingredient amounts are meant for.
+    pub servings: u32,
+    pub ingredients: Vec<Ingredient>,
+}
+
+pub type Recipe = RecipeImpl<i64>;
+
+#[cfg(feature = "ssr")]
+impl Recipe {
+    pub async fn select_by_id(id: i64, db: &DB) -> Result<Self> {
+        let mut conn = db.connection().await?;
+        let row = sqlx::query!("SELECT id, name, servings FROM recipe WHERE id = ?", id)
+            .fetch_one(conn.as_mut())
+            .await?;
+        let ingredients = Ingredient::for_recipe(row.id, conn.as_mut()).await?;
+        Ok(Recipe { id: row.id, name: row.name, servings: servings_as(row.servings), ingredients })
+    }
+
+    pub async fn select_all(db: &DB) -> Result<Vec<Self>> {
+        let mut conn = db.connection().await?;
+        let rows = sqlx::query!("SELECT id, name, servings FROM recipe ORDER BY name")
+            .fetch_all(conn.as_mut())
+            .await?;
+        let mut recipes = Vec::with_capacity(rows.len());
+        for row in rows {
+            let ingredients = Ingredient::for_recipe(row.id, conn.as_mut()).await?;
+            recipes.push(Recipe {
+                id: row.id,
+                name: row.name,
+                servings: servings_as(row.servings),
+                ingredients,
+            });
+        }
+        Ok(recipes)
+    }
+
+    /// Returns whether rows where affected or not
+    pub async fn remove(id: i64, db: &DB) -> Result<bool> {
+        let mut tx = db.begin_transaction().await?;
+        let affected = sqlx::query!("DELETE FROM recipe WHERE id = ?", id)
+            .execute(tx.as_mut())
+            .await?
+            .rows_affected()
+            > 0;
+
+        sqlx::query!("DELETE FROM recipe_ingredient WHERE ingredient_of = ?", id)
+            .execute(tx.as_mut())
+            .await?;
+
+        tx.commit().await?;
+        Ok(affected)
+    }
+}
+
+pub type NewRecipe = RecipeImpl<()>;
+
+impl Default for NewRecipe {
+    fn default() -> Self {
+        Self { id: (), name: "".to_string(), servings: 2, ingredients: vec![] }
+    }
+}
+
+impl NewRecipe {
+    #[cfg(feature = "ssr")]
+    pub async fn insert(self, db: &DB) -> Result<Recipe> {
+        logging::log!("insert recipe: {:?}", self);
+        let mut tx = db.begin_transaction().await?;
+
+        let servings = self.servings as i64;
+        let id = sqlx::query!(
+            "INSERT INTO recipe(name, servings) VALUES ( ?, ? )",
+            self.name,
+            servings
+        )
+        .execute(tx.as_mut())
+        .await?
+        .last_insert_rowid();
+
+        for ingredient in self.ingredients.iter() {
+            ingredient.insert(id, tx.as_mut()).await?;
+        }
+
+        tx.commit().await?;
+        Ok(Recipe { id, ..self })
+    }
+}
+
+/// An ingredient line of a recipe. If `barcode` is set, the ingredient is
+/// linked to that product, otherwise it is just free text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ingredient {
+    pub name: String,
+    pub amount: Amount,
+    pub barcode: OptionBarcode,
+}
+
+impl Default for Ingredient {
+    fn default() -> Self {
+        Self { name: "".to_string(), amount: Amount::default(), barcode: OptionBarcode::none() }
+    }
+}
+
+impl Ingredient {
+    /// Returns the amount needed for `servings` servings of a recipe which is
+    /// meant for `recipe_servings` servings. Countable amounts are rounded up.
+    pub fn scaled_amount(&self, servings: u32, recipe_servings: u32) -> Amount {
+        self.amount.scale(servings as f64 / recipe_servings.max(1) as f64)
+    }
+
+    /// Creates the list item for this ingredient. Linked products are looked
+    /// up by their barcode.
+    pub async fn to_new_item(&self, amount: Amount, lang: Language) -> NewItem {
+        let variant = match self.barcode.0 {
+            Some(barcode) => NewVariant::from_barcode(barcode, lang)
+                .await
+                .inspect_err(|e| logging::error!("NewVariant::from_barcode error: {}", e))
+                .unwrap_or_default(),
+            None => NewVariant::default(),
+        };
+        let name = if variant.name.trim().is_empty() { self.name.clone() } else { variant.name };
+        let variant = NewVariant { name, barcode: self.barcode, ..variant };
+        NewItem { amount, variants: vec![variant], ..NewItem::default() }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Ingredient {
+    pub async fn for_recipe(
+        recipe_id: i64,
+        conn: impl sqlx::Executor<'_, Database = DBType>,
+    ) -> Result<Vec<Self>> {
+        sqlx::query_as!(
+            IngredientRow,
+            r#"SELECT name, amount, unit, barcode as "barcode: Barcode"
+            FROM recipe_ingredient WHERE ingredient_of = ?"#,
+            recipe_id
+        )
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|IngredientRow { name, amount, unit, barcode }| {
+            let unit = Unit::try_from(unit.as_str())
+                .map_err(|unit| Error::InvalidDBValue(format!("unknown unit: {unit}")))?;
+            Ok(Ingredient { name, amount: Amount::new(amount, unit), barcode })
+        })
+        .collect()
+    }
+
+    pub async fn insert(
+        &self,
+        recipe_id: i64,
+        conn: impl sqlx::Executor<'_, Database = DBType>,
+    ) -> Result<()> {
+        let amount = self.amount.value();
+        let unit = self.amount.unit.as_str();
+        sqlx::query!(
+            r#"INSERT INTO recipe_ingredient(ingredient_of, name, amount, unit, barcode)
+            VALUES ( ?, ?, ?, ?, ? )"#,
+            recipe_id,
+            self.name,
+            amount,
+            unit,
+            self.barcode
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+struct IngredientRow {
+    name: String,
+    amount: f64,
+    unit: String,
+    barcode: OptionBarcode,
+}
+
+fn servings_as(int: i64) -> u32 {
+    int.clamp(1, u32::MAX as i64) as u32
+}
@@ -0,0 +1,272 @@
+pub mod data;
+pub mod server_functions;
+
+use self::{
+    data::{Ingredient, NewRecipe, Recipe},
+    server_functions::{get_recipes, AddRecipeToList, InsertRecipe, RemoveRecipe},
+};
+use crate::{barcode_scanner::OptionBarcode, item::amount::Unit, language::Language};
+use leptos::*;
+use leptos_router::*;
+
+#[component]
+pub fn RecipesPage() -> impl IntoView {
+    let insert_recipe = create_server_action::<InsertRecipe>();
+    let remove_recipe = create_server_action::<RemoveRecipe>();
+    let add_to_list = create_server_action::<AddRecipeToList>();
+
+    let recipes = create_resource(
+        move || (insert_recipe.version().get(), remove_recipe.version().get()),
+        |_| async {
+            get_recipes()
+                .await
+                .inspect_err(|err| logging::error!("ERROR while getting recipes: {}", err))
+                .unwrap_or_default()
+        },
+    );
+
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if add_to_list.value().with(|res| matches!(res, Some(Ok(_)))) {
+            navigate("/", Default::default());
+        }
+    });
+
+    view! {
+        <section id="recipes">
+            <A href="/">"Back to the list"</A>
+            <h1>"Recipes"</h1>
+            <ul class="recipe-list">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    {
+                        move || recipes().map(|recipes| {
+                            recipes
+                                .into_iter()
+                                .map(|recipe| view! {
+                                    <RecipeView recipe add_to_list remove_recipe />
+                                })
+                                .collect_view()
+                        })
+                    }
+                </Transition>
+            </ul>
+            <NewRecipeView insert_recipe />
+        </section>
+    }
+}
+
+#[component]
+pub fn RecipeView(
+    recipe: Recipe,
+    add_to_list: Action<AddRecipeToList, Result<Vec<i64>, ServerFnError>>,
+    remove_recipe: Action<RemoveRecipe, Result<bool, ServerFnError>>,
+) -> impl IntoView {
+    let Recipe { id, name, servings: recipe_servings, ingredients } = recipe;
+    let servings = create_rw_signal(recipe_servings);
+
+    let ingredients = ingredients
+        .into_iter()
+        .map(|ingredient| {
+            let is_linked = ingredient.barcode.0.is_some();
+            let name = ingredient.name.clone();
+            let amount = move || ingredient.scaled_amount(servings(), recipe_servings).to_string();
+            view! {
+                <li class="ingredient" class:linked=is_linked>
+                    <span class="amount">{ amount }</span>
+                    <span class="name">{ name }</span>
+                </li>
+            }
+        })
+        .collect_view();
+
+    let add = move |_| {
+        let lang = Language::from_navigator();
+        add_to_list.dispatch(AddRecipeToList { id, servings: servings.get(), lang })
+    };
+    let remove = move |_| match window().confirm_with_message("Remove Recipe?") {
+        Ok(ok) if ok => remove_recipe.dispatch(RemoveRecipe { id }),
+        _ => (),
+    };
+
+    view! {
+        <li class="recipe">
+            <div class="recipe-header">
+                <h3 class="name">{ name }</h3>
+                <label>
+                    "Servings: "
+                    <input
+                        type="number"
+                        min="1"
+                        class="servings"
+                        prop:value=servings
+                        on:change=move |ev| servings.update(
+                            |s| *s = event_target_value(&ev).parse().unwrap_or(*s).max(1)
+                        )
+                    />
+                </label>
+            </div>
+            <ul class="ingredients">{ ingredients }</ul>
+            <div class="buttons">
+                <button class="cursor-pointer" on:click=add>"Add to List"</button>
+                <button class="cursor-pointer" on:click=remove>"Remove"</button>
+            </div>
+        </li>
+    }
+}
+
+#[component]
+pub fn NewRecipeView(
+    insert_recipe: Action<InsertRecipe, Result<i64, ServerFnError>>,
+) -> impl IntoView {
+    let name = create_rw_signal(String::new());
+    let servings = create_rw_signal(NewRecipe::default().servings);
+    let ingredients = create_rw_signal(vec![Ingredient::default()]);
+
+    let reset = move || {
+        name.set(String::new());
+        servings.set(NewRecipe::default().servings);
+        ingredients.set(vec![Ingredient::default()]);
+    };
+
+    let ingredient_view = move |idx: usize| {
+        let get = move |f: fn(&Ingredient) -> String| {
+            move || ingredients.with(|v| v.get(idx).map(f).unwrap_or_default())
+        };
+        let set_barcode = move |ev| {
+            let barcode = match event_target_value(&ev) {
+                text if text.trim().is_empty() => OptionBarcode::none(),
+                text => match text.parse() {
+                    Ok(barcode) => OptionBarcode::some(barcode),
+                    Err(err) => {
+                        window().alert_with_message(&format!("invalid barcode: {err}")).unwrap();
+                        return;
+                    },
+                },
+            };
+            ingredients.update(|v| v[idx].barcode = barcode);
+        };
+        view! {
+            <li class="ingredient new">
+                <input
+                    type="number"
+                    min="0"
+                    class="amount"
+                    title="Amount"
+                    prop:value=get(|i| i.amount.value().to_string())
+                    on:change=move |ev| ingredients.update(|v| {
+                        let amount = &mut v[idx].amount;
+                        if let Ok(value) = event_target_value(&ev).replace(',', ".").parse() {
+                            *amount = amount.with_value(value);
+                        }
+                    })
+                />
+                <select
+                    class="unit"
+                    title="Unit"
+                    on:change=move |ev| {
+                        if let Ok(unit) = Unit::try_from(event_target_value(&ev).as_str()) {
+                            ingredients.update(|v| v[idx].amount.unit = unit)
+                        }
+                    }
+                >
+                    {
+                        Unit::ALL
+                            .into_iter()
+                            .map(|unit| view! {
+                                <option
+                                    value=unit.as_str()
+                                    selected=move || ingredients.with(|v| {
+                                        v.get(idx).is_some_and(|i| i.amount.unit == unit)
+                                    })
+                                >
+                                    { unit.as_str() }
+                                </option>
+                            })
+                            .collect_view()
+                    }
+                </select>
+                <input
+                    type="text"
+                    class="name"
+                    placeholder="Ingredient"
+                    title="Ingredient"
+                    prop:value=get(|i| i.name.clone())
+                    on:change=move |ev| ingredients.update(|v| v[idx].name = event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    class="barcode"
+                    placeholder="Barcode (optional)"
+                    title="Barcode of the linked product"
+                    prop:value=get(|i| i.barcode.0.map(|b| b.to_string()).unwrap_or_default())
+                    on:change=set_barcode
+                />
+            </li>
+        }
+    };
+
+    let save = move |_| {
+        let new_recipe = NewRecipe {
+            id: (),
+            name: name.get_untracked().trim().to_string(),
+            servings: servings.get_untracked(),
+            ingredients: ingredients
+                .get_untracked()
+                .into_iter()
+                .filter(|i| !i.name.trim().is_empty())
+                .collect(),
+        };
+        if new_recipe.name.is_empty() {
+            window().alert_with_message("empty Name not allowed").unwrap();
+        } else if new_recipe.ingredients.is_empty() {
+            window().alert_with_message("a Recipe needs at least one Ingredient").unwrap();
+        } else {
+            insert_recipe.dispatch(InsertRecipe { new_recipe });
+            reset();
+        }
+    };
+
+    view! {
+        <div class="recipe new">
+            <h3>"New Recipe"</h3>
+            <div class="recipe-header">
+                <input
+                    type="text"
+                    class="name"
+                    placeholder="Name"
+                    title="Name"
+                    prop:value=name
+                    on:change=move |ev| name.set(event_target_value(&ev))
+                />
+                <label>
+                    "Servings: "
+                    <input
+                        type="number"
+                        min="1"
+                        class="servings"
+                        prop:value=servings
+                        on:change=move |ev| servings.update(
+                            |s| *s = event_target_value(&ev).parse().unwrap_or(*s).max(1)
+                        )
+                    />
+                </label>
+            </div>
+            <ul class="ingredients">
+                <For
+                    each=move || 0..ingredients.with(Vec::len)
+                    key=|idx| *idx
+                    children=ingredient_view
+                />
+            </ul>
+            <div class="buttons">
+                <button
+                    class="cursor-pointer"
+                    on:click=move |_| ingredients.update(|v| v.push(Ingredient::default()))
+                >
+                    "Add Ingredient"
+                </button>
+                <button class="cursor-pointer" on:click=save>"Save Recipe"</button>
+            </div>
+        </div>
+    }
+}
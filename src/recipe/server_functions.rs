@@ -0,0 +1,39 @@
+use super::data::{NewRecipe, Recipe};
+use crate::{item::data::NewItem, language::Language};
+use leptos::{server, ServerFnError};
+
+#[server]
+pub async fn get_recipes() -> Result<Vec<Recipe>, ServerFnError> {
+    Ok(Recipe::select_all(&crate::db::MY_DB)
+        .await
+        .inspect_err(|err| eprintln!("ERROR (get_recipes): {}", err))?)
+}
+
+/// Returns id of the created recipe.
+#[server]
+pub async fn insert_recipe(new_recipe: NewRecipe) -> Result<i64, ServerFnError> {
+    Ok(new_recipe.insert(&crate::db::MY_DB).await?.id)
+}
+
+#[server]
+pub async fn remove_recipe(id: i64) -> Result<bool, ServerFnError> {
+    Recipe::remove(id, &crate::db::MY_DB).await.map_err(Into::into)
+}
+
+/// Adds the ingredients for `servings` servings to the list. Items which are
+/// already on the list are merged. Linked products are named in `lang`.
+/// Returns the ids of the inserted or updated items.
+#[server]
+pub async fn add_recipe_to_list(
+    id: i64,
+    servings: u32,
+    lang: Language,
+) -> Result<Vec<i64>, ServerFnError> {
+    let recipe = Recipe::select_by_id(id, &crate::db::MY_DB).await?;
+    let mut items = Vec::with_capacity(recipe.ingredients.len());
+    for ingredient in recipe.ingredients.iter() {
+        let amount = ingredient.scaled_amount(servings, recipe.servings);
+        items.push(ingredient.to_new_item(amount, lang).await);
+    }
+    Ok(NewItem::insert_or_merge_all(items, &crate::db::MY_DB).await?)
+}
@@ -0,0 +1,416 @@
+use crate::item::{
+    data::{Item, NewItem},
+    server_functions::get_list,
+    urgency::today,
+};
+#[cfg(feature = "ssr")]
+use crate::{db::DB, error::Error};
+use chrono::{Months, NaiveDate};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+use std::{fmt, time::Duration};
+
+/// How often the scheduler checks for due recurrences.
+#[cfg(feature = "ssr")]
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecurrenceUnit {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl RecurrenceUnit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecurrenceUnit::Day => "day",
+            RecurrenceUnit::Week => "week",
+            RecurrenceUnit::Month => "month",
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for RecurrenceUnit {
+    type Error = &'a str;
+
+    fn try_from(unit: &'a str) -> Result<Self, Self::Error> {
+        match unit {
+            "day" => Ok(RecurrenceUnit::Day),
+            "week" => Ok(RecurrenceUnit::Week),
+            "month" => Ok(RecurrenceUnit::Month),
+            unit => Err(unit),
+        }
+    }
+}
+
+/// "every `every` `unit`s"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurrenceRule {
+    pub every: u32,
+    pub unit: RecurrenceUnit,
+}
+
+impl RecurrenceRule {
+    /// Returns the first due date after `date`.
+    pub fn next_after(self, date: NaiveDate) -> NaiveDate {
+        let every = self.every.max(1);
+        match self.unit {
+            RecurrenceUnit::Day => date + chrono::Days::new(every as u64),
+            RecurrenceUnit::Week => date + chrono::Days::new(7 * every as u64),
+            RecurrenceUnit::Month => date + Months::new(every),
+        }
+    }
+
+    /// Returns the first due date after `today`, counting from `due`. Missed
+    /// dates, e.g. while the server was down, are skipped.
+    pub fn catch_up(self, due: NaiveDate, today: NaiveDate) -> NaiveDate {
+        let mut next_due = due;
+        while next_due <= today {
+            next_due = self.next_after(next_due);
+        }
+        next_due
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.every {
+            1 => write!(f, "every {}", self.unit.as_str()),
+            every => write!(f, "every {} {}s", every, self.unit.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub id: i64,
+    pub item_id: i64,
+    /// The item which is re-added if the original item was removed from the
+    /// list in the meantime.
+    pub item: NewItem,
+    pub rule: RecurrenceRule,
+    pub next_due: NaiveDate,
+}
+
+#[cfg(feature = "ssr")]
+impl Recurrence {
+    pub async fn select_all(db: &DB) -> Result<Vec<Self>, Error> {
+        let mut conn = db.connection().await?;
+        sqlx::query!(
+            r#"SELECT id, item_id, item, every, unit, next_due as "next_due: NaiveDate"
+            FROM recurrence ORDER BY next_due"#
+        )
+        .fetch_all(conn.as_mut())
+        .await?
+        .into_iter()
+        .map(|r| Self::from_row(r.id, r.item_id, &r.item, r.every, &r.unit, r.next_due))
+        .collect()
+    }
+
+    pub async fn select_due(today: NaiveDate, db: &DB) -> Result<Vec<Self>, Error> {
+        let mut conn = db.connection().await?;
+        sqlx::query!(
+            r#"SELECT id, item_id, item, every, unit, next_due as "next_due: NaiveDate"
+            FROM recurrence WHERE next_due <= ?"#,
+            today
+        )
+        .fetch_all(conn.as_mut())
+        .await?
+        .into_iter()
+        .map(|r| Self::from_row(r.id, r.item_id, &r.item, r.every, &r.unit, r.next_due))
+        .collect()
+    }
+
+    fn from_row(
+        id: i64,
+        item_id: i64,
+        item: &str,
+        every: i64,
+        unit: &str,
+        next_due: NaiveDate,
+    ) -> Result<Self, Error> {
+        let unit = RecurrenceUnit::try_from(unit)
+            .map_err(|unit| Error::InvalidDBValue(format!("unknown recurrence unit: {unit}")))?;
+        let every = every.clamp(1, u32::MAX as i64) as u32;
+        let item = serde_json::from_str(item)?;
+        Ok(Recurrence { id, item_id, item, rule: RecurrenceRule { every, unit }, next_due })
+    }
+
+    /// Returns the id of the new recurrence.
+    pub async fn insert(
+        item_id: i64,
+        item: NewItem,
+        rule: RecurrenceRule,
+        next_due: NaiveDate,
+        db: &DB,
+    ) -> Result<i64, Error> {
+        let item = serde_json::to_string(&item)?;
+        let every = rule.every.max(1) as i64;
+        let unit = rule.unit.as_str();
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!(
+            "INSERT INTO recurrence(item_id, item, every, unit, next_due) VALUES ( ?, ?, ?, ?, ? )",
+            item_id,
+            item,
+            every,
+            unit,
+            next_due
+        )
+        .execute(conn.as_mut())
+        .await?
+        .last_insert_rowid())
+    }
+
+    /// Returns whether rows where affected or not
+    pub async fn remove(id: i64, db: &DB) -> Result<bool, Error> {
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!("DELETE FROM recurrence WHERE id = ?", id)
+            .execute(conn.as_mut())
+            .await?
+            .rows_affected()
+            > 0)
+    }
+
+    /// Unchecks the item or re-adds it if it isn't on the list anymore and
+    /// moves `next_due` past `today`. If the amount of the item has been set
+    /// to zero, it is reset to the amount of the snapshot. Both happen in one
+    /// transaction, so the item isn't re-added twice if one of them fails.
+    pub async fn apply(self, today: NaiveDate, db: &DB) -> Result<(), Error> {
+        let mut tx = db.begin_transaction().await?;
+        let amount = self.item.amount.value();
+        let unit = self.item.amount.unit.as_str();
+        let is_on_list = sqlx::query!(
+            r#"UPDATE item SET
+                completed = FALSE,
+                amount = CASE WHEN amount = 0 THEN ? ELSE amount END,
+                unit = CASE WHEN amount = 0 THEN ? ELSE unit END
+            WHERE id = ?"#,
+            amount,
+            unit,
+            self.item_id
+        )
+        .execute(tx.as_mut())
+        .await?
+        .rows_affected()
+            > 0;
+
+        let item_id =
+            if is_on_list { self.item_id } else { self.item.merge_with(tx.as_mut()).await? };
+        let next_due = self.rule.catch_up(self.next_due, today);
+
+        sqlx::query!(
+            "UPDATE recurrence SET item_id = ?, next_due = ? WHERE id = ?",
+            item_id,
+            next_due,
+            self.id
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Periodically re-adds or unchecks the items of due recurrences.
+#[cfg(feature = "ssr")]
+pub async fn run_scheduler() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let today = today();
+        let due = match Recurrence::select_due(today, &crate::db::MY_DB).await {
+            Ok(due) => due,
+            Err(err) => {
+                logging::error!("ERROR (recurrence scheduler): {}", err);
+                continue;
+            },
+        };
+        for recurrence in due {
+            let id = recurrence.id;
+            match recurrence.apply(today, &crate::db::MY_DB).await {
+                Ok(()) => logging::log!("applied recurrence {id}"),
+                Err(err) => logging::error!("ERROR (recurrence scheduler) for {id}: {}", err),
+            }
+        }
+    }
+}
+
+#[server]
+pub async fn get_recurrences() -> Result<Vec<Recurrence>, ServerFnError> {
+    Ok(Recurrence::select_all(&crate::db::MY_DB).await?)
+}
+
+/// Returns the id of the new recurrence.
+#[server]
+pub async fn add_recurrence(
+    item_id: i64,
+    rule: RecurrenceRule,
+    next_due: NaiveDate,
+) -> Result<i64, ServerFnError> {
+    let item = Item::select_by_id(item_id, &crate::db::MY_DB).await?.without_id();
+    let item = NewItem { completed: false, ..item };
+    Ok(Recurrence::insert(item_id, item, rule, next_due, &crate::db::MY_DB).await?)
+}
+
+#[server]
+pub async fn remove_recurrence(id: i64) -> Result<bool, ServerFnError> {
+    Recurrence::remove(id, &crate::db::MY_DB).await.map_err(Into::into)
+}
+
+#[component]
+pub fn RecurrencesPage() -> impl IntoView {
+    let add_recurrence = create_server_action::<AddRecurrence>();
+    let remove_recurrence = create_server_action::<RemoveRecurrence>();
+
+    let recurrences = create_resource(
+        move || (add_recurrence.version().get(), remove_recurrence.version().get()),
+        |_| async {
+            get_recurrences()
+                .await
+                .inspect_err(|err| logging::error!("ERROR while getting recurrences: {}", err))
+                .unwrap_or_default()
+        },
+    );
+
+    let recurrence_view = move |Recurrence { id, item, rule, next_due, .. }: Recurrence| {
+        let remove = move |_| match window().confirm_with_message("Remove Recurrence?") {
+            Ok(ok) if ok => remove_recurrence.dispatch(RemoveRecurrence { id }),
+            _ => (),
+        };
+        view! {
+            <li class="recurrence" class:overdue=move || next_due < today()>
+                <span class="name">{ item.name().to_string() }</span>
+                <span class="rule sub-info">{ rule.to_string() }</span>
+                <span class="next-due">{ next_due.format("%a, %Y-%m-%d").to_string() }</span>
+                <button class="cursor-pointer" on:click=remove>"Remove"</button>
+            </li>
+        }
+    };
+
+    view! {
+        <section id="recurrences">
+            <A href="/">"Back to the list"</A>
+            <h1>"Recurring Items"</h1>
+            <ul class="recurrence-list">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    {
+                        move || recurrences().map(|recurrences| {
+                            recurrences.into_iter().map(recurrence_view).collect_view()
+                        })
+                    }
+                </Transition>
+            </ul>
+            <NewRecurrenceView add_recurrence />
+        </section>
+    }
+}
+
+#[component]
+pub fn NewRecurrenceView(
+    add_recurrence: Action<AddRecurrence, Result<i64, ServerFnError>>,
+) -> impl IntoView {
+    let items = create_local_resource(
+        || (),
+        |_| async { get_list().await.map(|list| list.0).unwrap_or_default() },
+    );
+
+    let item_id = create_rw_signal(None::<i64>);
+    let every = create_rw_signal(1u32);
+    let unit = create_rw_signal(RecurrenceUnit::default());
+    let next_due = create_rw_signal(today());
+
+    let save = move |_| match item_id.get_untracked() {
+        Some(item_id) => add_recurrence.dispatch(AddRecurrence {
+            item_id,
+            rule: RecurrenceRule { every: every.get_untracked(), unit: unit.get_untracked() },
+            next_due: next_due.get_untracked(),
+        }),
+        None => window().alert_with_message("no Item selected").unwrap(),
+    };
+
+    let item_option = |item: Item| {
+        view! { <option value=item.id>{ item.name().to_string() }</option> }
+    };
+
+    view! {
+        <div class="recurrence new">
+            <h3>"New Recurrence"</h3>
+            <select
+                class="item-select"
+                on:change=move |ev| item_id.set(event_target_value(&ev).parse().ok())
+            >
+                <option value="" selected>"Select Item"</option>
+                { move || items().map(|items| items.into_iter().map(item_option).collect_view()) }
+            </select>
+            <label>
+                "every "
+                <input
+                    type="number"
+                    min="1"
+                    class="every"
+                    prop:value=every
+                    on:change=move |ev| every.update(
+                        |e| *e = event_target_value(&ev).parse().unwrap_or(*e).max(1)
+                    )
+                />
+            </label>
+            <select
+                class="unit-select"
+                on:change=move |ev| {
+                    if let Ok(u) = RecurrenceUnit::try_from(event_target_value(&ev).as_str()) {
+                        unit.set(u)
+                    }
+                }
+            >
+                <option value="day">"day(s)"</option>
+                <option value="week" selected>"week(s)"</option>
+                <option value="month">"month(s)"</option>
+            </select>
+            <label>
+                " starting "
+                <input
+                    type="date"
+                    class="next-due"
+                    prop:value=move || next_due().format("%Y-%m-%d").to_string()
+                    on:change=move |ev| {
+                        if let Ok(date) = event_target_value(&ev).parse() {
+                            next_due.set(date)
+                        }
+                    }
+                />
+            </label>
+            <button class="cursor-pointer" on:click=save>"Save"</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_next_after() {
+        let rule = |every, unit| RecurrenceRule { every, unit };
+        assert_eq!(rule(3, RecurrenceUnit::Day).next_after(date(2024, 2, 27)), date(2024, 3, 1));
+        assert_eq!(rule(2, RecurrenceUnit::Week).next_after(date(2024, 1, 1)), date(2024, 1, 15));
+        assert_eq!(rule(1, RecurrenceUnit::Month).next_after(date(2024, 1, 31)), date(2024, 2, 29));
+        // 0 is treated as 1
+        assert_eq!(rule(0, RecurrenceUnit::Day).next_after(date(2024, 1, 1)), date(2024, 1, 2));
+    }
+
+    #[test]
+    fn test_catch_up() {
+        let weekly = RecurrenceRule { every: 1, unit: RecurrenceUnit::Week };
+        assert_eq!(weekly.catch_up(date(2024, 1, 1), date(2024, 1, 1)), date(2024, 1, 8));
+        assert_eq!(weekly.catch_up(date(2024, 1, 1), date(2024, 1, 20)), date(2024, 1, 22));
+        assert_eq!(weekly.catch_up(date(2024, 1, 1), date(2024, 1, 22)), date(2024, 1, 29));
+        assert_eq!(weekly.catch_up(date(2024, 2, 1), date(2024, 1, 22)), date(2024, 2, 1));
+    }
+}
@@ -0,0 +1,262 @@
+//! The continuous scan modes: products are added to the list or checked off
+//! one after another while the camera stays open.
+
+#[cfg(feature = "ssr")]
+use crate::item::{data::ItemRow, variant_data::NewVariant};
+use crate::{
+    barcode_scanner::{Barcode, BarcodeScanner, Gs1Data, OptionBarcode},
+    item::{
+        amount::Amount,
+        data::{Item, NewItem},
+        package::Package,
+        server_functions::set_completed,
+        RefreshList,
+    },
+    language::Language,
+    util::force_use_context,
+};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanMode {
+    /// Adds the scanned products to the list, e.g. when unpacking groceries.
+    Add,
+    /// Checks off the list items of the scanned products in the store.
+    CheckOff,
+}
+
+/// What a scan changed on the list, so it can be undone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScannedItem {
+    pub item_id: i64,
+    pub name: String,
+    pub amount: Amount,
+    /// The amount and whether the item was completed before the scan. `None`
+    /// if the item was added by the scan.
+    pub previous: Option<(Amount, bool)>,
+}
+
+/// Adds the product with `barcode` to the list. If it is already on the list,
+/// its amount is increased by one step like with the `+` button. A non-empty
+/// `package` replaces the one of the variant, also if the scan is undone.
+#[server]
+pub async fn scan_item(
+    barcode: Barcode,
+    package: Package,
+    lang: Language,
+) -> Result<ScannedItem, ServerFnError> {
+    let db = &crate::db::MY_DB;
+    let variant = NewVariant { barcode: OptionBarcode::some(barcode), ..NewVariant::default() };
+    let existing = {
+        let mut conn = db.connection().await?;
+        ItemRow::find_matching(&[variant], conn.as_mut()).await?
+    };
+
+    let Some(id) = existing else {
+        let mut item = NewItem::from_barcode(barcode, lang).await?;
+        item.variants.iter_mut().for_each(|v| v.package = package.clone());
+        let item = item.insert(db).await?;
+        crate::image_store::wake();
+        let name = item.name().to_string();
+        return Ok(ScannedItem { item_id: item.id, name, amount: item.amount, previous: None });
+    };
+
+    // The increment happens in SQL, so overlapping scans and `+` presses aren't lost.
+    let mut tx = db.begin_transaction().await?;
+    let row = ItemRow::select_by_id(id, tx.as_mut()).await?;
+    let amount = row.amount()?;
+    let step = amount.unit.step();
+    sqlx::query!("UPDATE item SET amount = amount + ?, completed = FALSE WHERE id = ?", step, id)
+        .execute(tx.as_mut())
+        .await?;
+    if !package.is_empty() {
+        sqlx::query!(
+            "UPDATE item_variant SET package = ? WHERE variant_of = ? AND barcode = ?",
+            package,
+            id,
+            barcode
+        )
+        .execute(tx.as_mut())
+        .await?;
+    }
+    tx.commit().await?;
+
+    let name = Item::select_by_id(id, db).await?.name().to_string();
+    let previous = Some((amount, row.completed));
+    Ok(ScannedItem { item_id: id, name, amount: amount.step_up(), previous })
+}
+
+/// Removes the added item or restores the previous amount.
+#[server]
+pub async fn undo_scan(scanned: ScannedItem) -> Result<(), ServerFnError> {
+    let db = &crate::db::MY_DB;
+    let Some((amount, completed)) = scanned.previous else {
+        Item::remove(scanned.item_id, db).await?;
+        return Ok(());
+    };
+    let (value, unit) = (amount.value(), amount.unit.as_str());
+    let mut conn = db.connection().await?;
+    sqlx::query!(
+        "UPDATE item SET amount = ?, unit = ?, completed = ? WHERE id = ?",
+        value,
+        unit,
+        completed,
+        scanned.item_id
+    )
+    .execute(conn.as_mut())
+    .await?;
+    Ok(())
+}
+
+/// Returns the list items which have a variant with `barcode`. Items which
+/// aren't completed yet come first.
+#[server]
+pub async fn find_items_by_barcode(barcode: Barcode) -> Result<Vec<Item>, ServerFnError> {
+    let db = &crate::db::MY_DB;
+    let ids = {
+        let mut conn = db.connection().await?;
+        sqlx::query_scalar!(
+            "SELECT DISTINCT variant_of FROM item_variant WHERE barcode = ?",
+            barcode
+        )
+        .fetch_all(conn.as_mut())
+        .await?
+    };
+    let mut items = Vec::with_capacity(ids.len());
+    for id in ids {
+        items.push(Item::select_by_id(id, db).await?);
+    }
+    items.sort_by_key(|item| item.completed);
+    Ok(items)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum LogEntry {
+    Pending(Barcode),
+    Scanned(ScannedItem),
+    CheckedOff { item_id: i64, name: String },
+    AlreadyCompleted(String),
+    NotOnList(Barcode),
+    /// The name of the item.
+    Undone(String),
+    Failed(Barcode, String),
+}
+
+#[component]
+pub fn ContinuousScanView(mode: ScanMode) -> impl IntoView {
+    let refresh_list = force_use_context::<RefreshList>().0;
+    let log = create_rw_signal(Vec::<LogEntry>::new());
+    let set_entry = move |idx: usize, entry: LogEntry| {
+        log.update(|l| l[idx] = entry);
+        refresh_list.notify();
+    };
+
+    // Reported by the scanner right before the barcode of a GS1 code.
+    let gs1_data = store_value(None::<Gs1Data>);
+
+    let add = move |idx: usize, barcode: Barcode, package: Package| {
+        log.update(|l| l[idx] = LogEntry::Pending(barcode));
+        spawn_local(async move {
+            match scan_item(barcode, package, Language::from_navigator()).await {
+                Ok(scanned) => set_entry(idx, LogEntry::Scanned(scanned)),
+                Err(err) => set_entry(idx, LogEntry::Failed(barcode, err.to_string())),
+            }
+        });
+    };
+
+    let check_off = move |idx: usize, barcode: Barcode| {
+        spawn_local(async move {
+            let item = match find_items_by_barcode(barcode).await {
+                Ok(items) => items.into_iter().next(),
+                Err(err) => return set_entry(idx, LogEntry::Failed(barcode, err.to_string())),
+            };
+            let Some(item) = item else { return set_entry(idx, LogEntry::NotOnList(barcode)) };
+            let name = item.name().to_string();
+            let entry = if item.completed {
+                LogEntry::AlreadyCompleted(name)
+            } else {
+                match set_completed(item.id, true).await {
+                    Ok(()) => LogEntry::CheckedOff { item_id: item.id, name },
+                    Err(err) => LogEntry::Failed(barcode, err.to_string()),
+                }
+            };
+            set_entry(idx, entry);
+        });
+    };
+
+    let scan = move |barcode: Barcode| {
+        let data = gs1_data.get_value().filter(|d| d.gtin == Some(barcode));
+        let package = data.as_ref().map(Package::from).unwrap_or_default();
+        gs1_data.set_value(None);
+        let idx = log.with_untracked(Vec::len);
+        log.update(|l| l.push(LogEntry::Pending(barcode)));
+        match mode {
+            ScanMode::Add => add(idx, barcode, package),
+            ScanMode::CheckOff => check_off(idx, barcode),
+        }
+    };
+
+    let undo = move |idx: usize, entry: LogEntry| {
+        spawn_local(async move {
+            let (res, name) = match entry {
+                LogEntry::Scanned(scanned) => (undo_scan(scanned.clone()).await, scanned.name),
+                LogEntry::CheckedOff { item_id, name } => {
+                    (set_completed(item_id, false).await, name)
+                },
+                _ => return,
+            };
+            match res {
+                Ok(()) => set_entry(idx, LogEntry::Undone(name)),
+                Err(err) => logging::error!("ERROR while undoing the scan: {}", err),
+            }
+        })
+    };
+
+    let entry_view = move |(idx, entry): (usize, LogEntry)| {
+        let (class, text) = match &entry {
+            LogEntry::Pending(barcode) => ("pending", format!("{barcode}: looking up…")),
+            LogEntry::Scanned(ScannedItem { name, previous: None, .. }) => {
+                ("", format!("{name}: added"))
+            },
+            LogEntry::Scanned(ScannedItem { name, amount, .. }) => {
+                ("", format!("{name}: now {amount}"))
+            },
+            LogEntry::CheckedOff { name, .. } => ("", format!("{name}: checked off")),
+            LogEntry::AlreadyCompleted(name) => ("pending", format!("{name}: already checked off")),
+            LogEntry::NotOnList(barcode) => ("error", format!("{barcode}: not on your list")),
+            LogEntry::Undone(name) => ("undone", format!("{name}: undone")),
+            LogEntry::Failed(barcode, err) => ("error", format!("{barcode}: {err}")),
+        };
+        let button = match entry {
+            LogEntry::Scanned(_) | LogEntry::CheckedOff { .. } => Some(view! {
+                <button class="cursor-pointer" on:click=move |_| undo(idx, entry.clone())>
+                    "Undo"
+                </button>
+            }),
+            LogEntry::NotOnList(barcode) => Some(view! {
+                <button
+                    class="cursor-pointer"
+                    on:click=move |_| add(idx, barcode, Package::default())
+                >
+                    "Add it?"
+                </button>
+            }),
+            _ => None,
+        };
+        view! { <li class=class>{ text }{ button }</li> }
+    };
+
+    view! {
+        <div class="continuous-scan" on:click=|ev| ev.stop_propagation()>
+            <BarcodeScanner
+                continuous=true
+                set_gs1_data=move |data| gs1_data.set_value(Some(data))
+                set_barcode=scan
+            />
+            <ul class="scan-log">
+                { move || log().into_iter().enumerate().rev().map(entry_view).collect_view() }
+            </ul>
+        </div>
+    }
+}
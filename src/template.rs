@@ -0,0 +1,220 @@
+use crate::{
+    item::{
+        data::{Item, NewItem},
+        server_functions::get_list,
+        RefreshList,
+    },
+    popup::PopupSignal,
+    util::force_use_context,
+};
+#[cfg(feature = "ssr")]
+use crate::{db::DB, error::Error};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A named set of items which can be added to the list at once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Template {
+    pub id: i64,
+    pub name: String,
+    pub items: Vec<NewItem>,
+}
+
+#[cfg(feature = "ssr")]
+impl Template {
+    pub async fn select_all(db: &DB) -> Result<Vec<Self>, Error> {
+        let mut conn = db.connection().await?;
+        sqlx::query!("SELECT id, name, items FROM template ORDER BY name")
+            .fetch_all(conn.as_mut())
+            .await?
+            .into_iter()
+            .map(|row| {
+                let items = serde_json::from_str(&row.items)?;
+                Ok(Template { id: row.id, name: row.name, items })
+            })
+            .collect()
+    }
+
+    pub async fn select_by_id(id: i64, db: &DB) -> Result<Self, Error> {
+        let mut conn = db.connection().await?;
+        let row = sqlx::query!("SELECT id, name, items FROM template WHERE id = ?", id)
+            .fetch_one(conn.as_mut())
+            .await?;
+        Ok(Template { id: row.id, name: row.name, items: serde_json::from_str(&row.items)? })
+    }
+
+    /// Returns the id of the new template.
+    pub async fn insert(name: String, items: Vec<NewItem>, db: &DB) -> Result<i64, Error> {
+        let items = serde_json::to_string(&items)?;
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!("INSERT INTO template(name, items) VALUES ( ?, ? )", name, items)
+            .execute(conn.as_mut())
+            .await?
+            .last_insert_rowid())
+    }
+
+    /// Returns whether rows where affected or not
+    pub async fn remove(id: i64, db: &DB) -> Result<bool, Error> {
+        let mut conn = db.connection().await?;
+        Ok(sqlx::query!("DELETE FROM template WHERE id = ?", id)
+            .execute(conn.as_mut())
+            .await?
+            .rows_affected()
+            > 0)
+    }
+}
+
+#[server]
+pub async fn get_templates() -> Result<Vec<Template>, ServerFnError> {
+    Ok(Template::select_all(&crate::db::MY_DB).await?)
+}
+
+/// Creates a template from the list items with the ids `item_ids`. Returns the
+/// id of the template.
+#[server]
+pub async fn create_template(name: String, item_ids: Vec<i64>) -> Result<i64, ServerFnError> {
+    let name = name.trim().to_string();
+    let mut items = Vec::with_capacity(item_ids.len());
+    for id in item_ids {
+        let item = Item::select_by_id(id, &crate::db::MY_DB).await?.without_id();
+        items.push(NewItem { completed: false, ..item });
+    }
+    Ok(Template::insert(name, items, &crate::db::MY_DB).await?)
+}
+
+/// Adds all items of the template to the list. Items which are already on the
+/// list are merged. Returns the ids of the inserted or updated items.
+#[server]
+pub async fn apply_template(id: i64) -> Result<Vec<i64>, ServerFnError> {
+    let template = Template::select_by_id(id, &crate::db::MY_DB).await?;
+    Ok(NewItem::insert_or_merge_all(template.items, &crate::db::MY_DB).await?)
+}
+
+#[server]
+pub async fn remove_template(id: i64) -> Result<bool, ServerFnError> {
+    Template::remove(id, &crate::db::MY_DB).await.map_err(Into::into)
+}
+
+#[component]
+pub fn TemplatesView(popup: PopupSignal) -> impl IntoView {
+    let refresh_list = force_use_context::<RefreshList>().0;
+
+    let create_template = create_server_action::<CreateTemplate>();
+    let apply_template = create_server_action::<ApplyTemplate>();
+    let remove_template = create_server_action::<RemoveTemplate>();
+
+    let templates = create_local_resource(
+        move || (create_template.version().get(), remove_template.version().get()),
+        |_| async {
+            get_templates()
+                .await
+                .inspect_err(|err| logging::error!("ERROR while getting templates: {}", err))
+                .unwrap_or_default()
+        },
+    );
+
+    create_effect(move |_| {
+        if apply_template.value().with(|res| matches!(res, Some(Ok(_)))) {
+            refresh_list.notify();
+            popup.close();
+        }
+    });
+
+    let template_view = move |Template { id, name, items }: Template| {
+        let remove = move |_| match window().confirm_with_message("Remove Template?") {
+            Ok(ok) if ok => remove_template.dispatch(RemoveTemplate { id }),
+            _ => (),
+        };
+        view! {
+            <li class="template">
+                <span class="name">{ name }</span>
+                <span class="sub-info">{ format!("{} items", items.len()) }</span>
+                <button
+                    class="cursor-pointer"
+                    on:click=move |_| apply_template.dispatch(ApplyTemplate { id })
+                >
+                    "Apply"
+                </button>
+                <button class="cursor-pointer" on:click=remove>"Remove"</button>
+            </li>
+        }
+    };
+
+    let list_items = create_local_resource(
+        || (),
+        |_| async { get_list().await.map(|list| list.0).unwrap_or_default() },
+    );
+    let excluded = create_rw_signal(HashSet::<i64>::new());
+    let new_name = create_rw_signal(String::new());
+
+    let list_item_view = move |item: Item| {
+        let id = item.id;
+        view! {
+            <li>
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || excluded.with(|e| !e.contains(&id))
+                        on:input=move |ev| excluded.update(|e| {
+                            if event_target_checked(&ev) { e.remove(&id) } else { e.insert(id) };
+                        })
+                    />
+                    { item.name().to_string() }
+                </label>
+            </li>
+        }
+    };
+
+    let save = move |_| {
+        let name = new_name.get_untracked();
+        let item_ids = list_items
+            .get_untracked()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| item.id)
+            .filter(|id| excluded.with_untracked(|e| !e.contains(id)))
+            .collect::<Vec<_>>();
+        if name.trim().is_empty() {
+            window().alert_with_message("empty Name not allowed").unwrap();
+        } else if item_ids.is_empty() {
+            window().alert_with_message("no Items selected").unwrap();
+        } else {
+            create_template.dispatch(CreateTemplate { name, item_ids });
+            new_name.set(String::new());
+        }
+    };
+
+    view! {
+        <div class="templates" on:click=|ev| ev.stop_propagation()>
+            <h3>"Templates"</h3>
+            <ul class="template-list">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    {
+                        move || templates().map(|templates| {
+                            templates.into_iter().map(template_view).collect_view()
+                        })
+                    }
+                </Transition>
+            </ul>
+            <h3>"Save List as Template"</h3>
+            <input
+                type="text"
+                class="template-name"
+                placeholder="Template name"
+                prop:value=new_name
+                on:input=move |ev| new_name.set(event_target_value(&ev))
+            />
+            <ul class="template-items">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    {
+                        move || list_items().map(|items| {
+                            items.into_iter().map(list_item_view).collect_view()
+                        })
+                    }
+                </Transition>
+            </ul>
+            <button class="cursor-pointer" on:click=save>"Save"</button>
+        </div>
+    }
+}
@@ -146,3 +146,38 @@ impl<T> VecExt<T> for Vec<T> {
         self
     }
 }
+
+/// Implements the sqlx traits for a serde type, so it can be stored as a JSON
+/// `TEXT` column.
+macro_rules! json_column {
+    ($ty:ty) => {
+        #[cfg(feature = "ssr")]
+        impl sqlx::Type<sqlx::Sqlite> for $ty {
+            fn type_info() -> <sqlx::Sqlite as sqlx::Database>::TypeInfo {
+                <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+            }
+        }
+
+        #[cfg(feature = "ssr")]
+        impl sqlx::Encode<'_, sqlx::Sqlite> for $ty {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <sqlx::Sqlite as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
+            ) -> sqlx::encode::IsNull {
+                let json = serde_json::to_string(self).expect("can serialize to JSON");
+                <String as sqlx::Encode<'_, sqlx::Sqlite>>::encode(json, buf)
+            }
+        }
+
+        #[cfg(feature = "ssr")]
+        impl sqlx::Decode<'_, sqlx::Sqlite> for $ty {
+            fn decode(
+                value: <sqlx::Sqlite as sqlx::database::HasValueRef<'_>>::ValueRef,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let json = <&str as sqlx::Decode<'_, sqlx::Sqlite>>::decode(value)?;
+                Ok(serde_json::from_str(json)?)
+            }
+        }
+    };
+}
+pub(crate) use json_column;